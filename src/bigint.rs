@@ -0,0 +1,509 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, AddAssign, Mul, Neg},
+};
+
+/// Largest power of ten that fits in a limb, used when converting to and from decimal strings.
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+const DECIMAL_CHUNK_DIGITS: usize = 9;
+
+/// Arbitrary-precision signed integer stored as sign and magnitude.
+///
+/// The magnitude is a little-endian vector of 32 bit limbs without trailing zero limbs, so zero
+/// is an empty vector and is never negative.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    mag: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn from_i128(n: i128) -> Self {
+        let abs = n.unsigned_abs();
+        let mut mag = (0..4).map(|i| (abs >> (32 * i)) as u32).collect();
+        trim(&mut mag);
+
+        Self {
+            negative: n < 0,
+            mag,
+        }
+    }
+
+    /// Builds a non-negative integer from a string of ASCII decimal digits.
+    ///
+    /// The caller is responsible for validating the digits.
+    pub fn from_decimal_digits(digits: &[u8]) -> Self {
+        let mut mag = Vec::with_capacity(digits.len() / DECIMAL_CHUNK_DIGITS + 1);
+
+        let first_len = match digits.len() % DECIMAL_CHUNK_DIGITS {
+            0 => DECIMAL_CHUNK_DIGITS,
+            n => n,
+        };
+        let (first, rest) = digits.split_at(first_len.min(digits.len()));
+
+        mul_add_small(&mut mag, 1, chunk_value(first));
+        for chunk in rest.chunks(DECIMAL_CHUNK_DIGITS) {
+            mul_add_small(&mut mag, DECIMAL_CHUNK, chunk_value(chunk));
+        }
+
+        Self {
+            negative: false,
+            mag,
+        }
+    }
+
+    /// Returns the value as an `i128` if it fits.
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.mag.len() > 4 {
+            return None;
+        }
+
+        let abs = self
+            .mag
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &limb| (acc << 32) | u128::from(limb));
+        if self.negative {
+            0i128.checked_sub_unsigned(abs)
+        } else {
+            i128::try_from(abs).ok()
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            negative: false,
+            mag: self.mag.clone(),
+        }
+    }
+
+    /// Truncating division returning `(quotient, remainder)`, where the remainder has the sign of
+    /// `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &BigInt) -> (BigInt, BigInt) {
+        assert!(!divisor.is_zero(), "attempt to divide by zero");
+
+        let (quotient, remainder) = div_rem_mag(&self.mag, &divisor.mag);
+        let quotient = Self {
+            negative: self.negative != divisor.negative && !quotient.is_empty(),
+            mag: quotient,
+        };
+        let remainder = Self {
+            negative: self.negative && !remainder.is_empty(),
+            mag: remainder,
+        };
+
+        (quotient, remainder)
+    }
+
+    /// Returns `self * 10^exp`.
+    pub fn mul_pow10(&self, mut exp: u32) -> Self {
+        let mut mag = self.mag.clone();
+        while exp > 0 {
+            let step = exp.min(DECIMAL_CHUNK_DIGITS as u32);
+            mul_add_small(&mut mag, 10u32.pow(step), 0);
+            exp -= step;
+        }
+
+        Self {
+            negative: self.negative,
+            mag,
+        }
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(mut self) -> Self::Output {
+        if !self.is_zero() {
+            self.negative = !self.negative;
+        }
+        self
+    }
+}
+
+impl AddAssign<&BigInt> for BigInt {
+    fn add_assign(&mut self, rhs: &BigInt) {
+        if self.negative == rhs.negative {
+            add_mag(&mut self.mag, &rhs.mag);
+            return;
+        }
+
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => {
+                let mut mag = rhs.mag.clone();
+                sub_mag(&mut mag, &self.mag);
+                self.mag = mag;
+                self.negative = rhs.negative;
+            }
+            Ordering::Equal => *self = Self::zero(),
+            Ordering::Greater => sub_mag(&mut self.mag, &rhs.mag),
+        }
+    }
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(mut self, rhs: BigInt) -> Self::Output {
+        self += &rhs;
+        self
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> Self::Output {
+        if self.is_zero() || rhs.is_zero() {
+            return BigInt::zero();
+        }
+
+        let mut mag = vec![0u32; self.mag.len() + rhs.mag.len()];
+        for (i, &a) in self.mag.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.mag.iter().enumerate() {
+                let product = u64::from(a) * u64::from(b) + u64::from(mag[i + j]) + carry;
+                mag[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            mag[i + rhs.mag.len()] = carry as u32;
+        }
+        trim(&mut mag);
+
+        BigInt {
+            negative: self.negative != rhs.negative,
+            mag,
+        }
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return f.pad_integral(true, "", "0");
+        }
+
+        let mut mag = self.mag.clone();
+        let mut chunks = Vec::new();
+        while !mag.is_empty() {
+            chunks.push(div_rem_small(&mut mag, DECIMAL_CHUNK));
+        }
+
+        let mut digits = String::with_capacity(chunks.len() * DECIMAL_CHUNK_DIGITS);
+        let mut chunks = chunks.into_iter().rev();
+        if let Some(first) = chunks.next() {
+            digits.push_str(&first.to_string());
+        }
+        for chunk in chunks {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+
+        f.pad_integral(!self.negative, "", &digits)
+    }
+}
+
+fn chunk_value(digits: &[u8]) -> u32 {
+    digits
+        .iter()
+        .fold(0, |acc, &d| acc * 10 + u32::from(d - b'0'))
+}
+
+fn trim(mag: &mut Vec<u32>) {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+/// `mag = mag * mul + add`
+fn mul_add_small(mag: &mut Vec<u32>, mul: u32, add: u32) {
+    let mut carry = u64::from(add);
+    for limb in mag.iter_mut() {
+        let product = u64::from(*limb) * u64::from(mul) + carry;
+        *limb = product as u32;
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        mag.push(carry as u32);
+    }
+    trim(mag);
+}
+
+/// `mag = mag / div`, returning the remainder.
+fn div_rem_small(mag: &mut Vec<u32>, div: u32) -> u32 {
+    let mut rem = 0u64;
+    for limb in mag.iter_mut().rev() {
+        let cur = (rem << 32) | u64::from(*limb);
+        *limb = (cur / u64::from(div)) as u32;
+        rem = cur % u64::from(div);
+    }
+    trim(mag);
+    rem as u32
+}
+
+/// `a += b`
+fn add_mag(a: &mut Vec<u32>, b: &[u32]) {
+    if a.len() < b.len() {
+        a.resize(b.len(), 0);
+    }
+
+    let mut carry = 0u64;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let sum = u64::from(*limb) + u64::from(b.get(i).copied().unwrap_or(0)) + carry;
+        *limb = sum as u32;
+        carry = sum >> 32;
+        if carry == 0 && i >= b.len() {
+            break;
+        }
+    }
+    if carry > 0 {
+        a.push(carry as u32);
+    }
+}
+
+/// `a -= b`, where `a >= b`.
+fn sub_mag(a: &mut Vec<u32>, b: &[u32]) {
+    let mut borrow = 0i64;
+    for (i, limb) in a.iter_mut().enumerate() {
+        let diff = i64::from(*limb) - i64::from(b.get(i).copied().unwrap_or(0)) - borrow;
+        if diff < 0 {
+            *limb = (diff + (1 << 32)) as u32;
+            borrow = 1;
+        } else {
+            *limb = diff as u32;
+            borrow = 0;
+            if i >= b.len() {
+                break;
+            }
+        }
+    }
+    trim(a);
+}
+
+/// Long division of magnitudes (Knuth, TAOCP vol. 2, algorithm D), returning
+/// `(quotient, remainder)`.
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_mag(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if let [divisor] = b {
+        let mut quotient = a.to_vec();
+        let remainder = div_rem_small(&mut quotient, *divisor);
+        let mut remainder = vec![remainder];
+        trim(&mut remainder);
+        return (quotient, remainder);
+    }
+
+    // Normalize so the divisor's top limb has its high bit set, which keeps the quotient digit
+    // estimate off by at most two.
+    let shift = b[b.len() - 1].leading_zeros();
+    let b = shl_bits(b, shift);
+    let mut a = shl_bits(a, shift);
+    a.push(0);
+
+    let n = b.len();
+    let m = a.len() - n;
+    let top = u64::from(b[n - 1]);
+    let second = u64::from(b[n - 2]);
+    let mut quotient = vec![0u32; m];
+
+    for j in (0..m).rev() {
+        let numerator = (u64::from(a[j + n]) << 32) | u64::from(a[j + n - 1]);
+        let mut q_hat = numerator / top;
+        let mut r_hat = numerator % top;
+        while q_hat > u64::from(u32::MAX)
+            || q_hat * second > ((r_hat << 32) | u64::from(a[j + n - 2]))
+        {
+            q_hat -= 1;
+            r_hat += top;
+            if r_hat > u64::from(u32::MAX) {
+                break;
+            }
+        }
+
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+        for i in 0..n {
+            let product = q_hat * u64::from(b[i]) + carry;
+            carry = product >> 32;
+            let diff = i64::from(a[i + j]) - borrow - (product & u64::from(u32::MAX)) as i64;
+            a[i + j] = diff as u32;
+            borrow = i64::from(diff < 0);
+        }
+        let diff = i64::from(a[j + n]) - borrow - carry as i64;
+        a[j + n] = diff as u32;
+
+        if diff < 0 {
+            q_hat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u64::from(a[i + j]) + u64::from(b[i]) + carry;
+                a[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            a[j + n] = a[j + n].wrapping_add(carry as u32);
+        }
+
+        quotient[j] = q_hat as u32;
+    }
+
+    trim(&mut quotient);
+    a.truncate(n);
+    let mut remainder = shr_bits(&a, shift);
+    trim(&mut remainder);
+    (quotient, remainder)
+}
+
+fn shl_bits(mag: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return mag.to_vec();
+    }
+
+    let mut shifted = Vec::with_capacity(mag.len() + 1);
+    let mut carry = 0;
+    for &limb in mag {
+        shifted.push((limb << shift) | carry);
+        carry = limb >> (32 - shift);
+    }
+    if carry > 0 {
+        shifted.push(carry);
+    }
+    shifted
+}
+
+fn shr_bits(mag: &[u32], shift: u32) -> Vec<u32> {
+    if shift == 0 {
+        return mag.to_vec();
+    }
+
+    let mut shifted = vec![0; mag.len()];
+    for i in 0..mag.len() {
+        let high = mag.get(i + 1).map_or(0, |&limb| limb << (32 - shift));
+        shifted[i] = (mag[i] >> shift) | high;
+    }
+    shifted
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big(digits: &str) -> BigInt {
+        match digits.strip_prefix('-') {
+            Some(digits) => -BigInt::from_decimal_digits(digits.as_bytes()),
+            None => BigInt::from_decimal_digits(digits.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn can_round_trip_decimal_digits() {
+        for digits in ["0", "7", "4294967296", "123456789012345678901234567890"] {
+            assert_eq!(big(digits).to_string(), digits);
+        }
+    }
+
+    #[test]
+    fn can_add_with_carry_and_mixed_signs() {
+        assert_eq!((big("4294967295") + big("1")).to_string(), "4294967296");
+        assert_eq!((big("-4294967296") + big("1")).to_string(), "-4294967295");
+        assert_eq!((big("5") + big("-12")).to_string(), "-7");
+        assert_eq!((big("12") + big("-12")), BigInt::zero());
+    }
+
+    #[test]
+    fn can_convert_from_i128() {
+        assert_eq!(BigInt::from_i128(0), BigInt::zero());
+        assert_eq!(
+            BigInt::from_i128(i128::MIN).to_string(),
+            i128::MIN.to_string()
+        );
+        assert_eq!(
+            BigInt::from_i128(i128::MAX).to_string(),
+            i128::MAX.to_string()
+        );
+    }
+
+    #[test]
+    fn can_multiply_and_divide() {
+        let a = big("-123456789012345678901234567890");
+        let b = big("98765432109876543210");
+        let product = &a * &b;
+        assert_eq!(
+            product.to_string(),
+            "-12193263113702179522496570642237463801111263526900"
+        );
+
+        let (q, r) = product.div_rem(&b);
+        assert_eq!((q, r), (a.clone(), BigInt::zero()));
+
+        let (q, r) = a.div_rem(&b);
+        assert_eq!(
+            (q.to_string(), r.to_string()),
+            ("-1249999988".to_owned(), "-60185185207253086410".to_owned())
+        );
+
+        let (q, r) =
+            big("340282366920938463463374607431768211456").div_rem(&big("18446744073709551617"));
+        assert_eq!(
+            (q.to_string(), r.to_string()),
+            ("18446744073709551615".to_owned(), "1".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_convert_to_i128_when_it_fits() {
+        assert_eq!(BigInt::from_i128(i128::MIN).to_i128(), Some(i128::MIN));
+        assert_eq!(
+            big("170141183460469231731687303715884105728").to_i128(),
+            None
+        );
+    }
+
+    #[test]
+    fn can_multiply_by_power_of_ten() {
+        assert_eq!(
+            big("-25").mul_pow10(20).to_string(),
+            "-2500000000000000000000"
+        );
+    }
+}
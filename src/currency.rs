@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+
+/// Currency symbols and the ISO 4217 code each is read as. Symbols shared by several
+/// currencies, such as `$` and `¥`, are read as the most traded one.
+const SYMBOLS: &[(char, &str)] = &[
+    ('$', "USD"),
+    ('€', "EUR"),
+    ('£', "GBP"),
+    ('¥', "JPY"),
+    ('₹', "INR"),
+    ('₩', "KRW"),
+    ('₽', "RUB"),
+    ('₺', "TRY"),
+    ('₴', "UAH"),
+    ('₪', "ILS"),
+    ('₫', "VND"),
+    ('₦', "NGN"),
+    ('฿', "THB"),
+    ('₱', "PHP"),
+];
+
+/// ISO 4217 codes of the currencies recognized before or after an amount.
+const CODES: &[&str] = &[
+    "AED", "ARS", "AUD", "BRL", "CAD", "CHF", "CLP", "CNY", "COP", "CZK", "DKK", "EGP", "EUR",
+    "GBP", "HKD", "HUF", "IDR", "ILS", "INR", "ISK", "JPY", "KRW", "MXN", "MYR", "NGN", "NOK",
+    "NZD", "PEN", "PHP", "PKR", "PLN", "RON", "RUB", "SAR", "SEK", "SGD", "THB", "TRY", "TWD",
+    "UAH", "USD", "VND", "ZAR",
+];
+
+/// ISO 4217 code of `word` if it is a currency symbol or code on its own, such as the `€` of
+/// `12 €`.
+pub fn currency_of(word: &str) -> Option<&'static str> {
+    let mut chars = word.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if let Some(&(_, code)) = SYMBOLS.iter().find(|&&(symbol, _)| symbol == c) {
+            return Some(code);
+        }
+    }
+
+    CODES.iter().find(|&&code| code == word).copied()
+}
+
+/// Splits the currency off an amount such as `$1,200.50`, `12.00 USD` or `-€30`, returning its
+/// ISO 4217 code and the amount without it.
+///
+/// A symbol and a code may both be given if they agree, as in `$12 USD`. Fails if the amount is
+/// marked with two different currencies.
+pub fn split_currency(num_str: &str) -> Result<(Option<&'static str>, Cow<'_, str>), String> {
+    let may_have_currency = num_str
+        .chars()
+        .any(|c| c.is_ascii_uppercase() || c == '$' || (!c.is_ascii() && is_currency_symbol(c)));
+    if !may_have_currency {
+        return Ok((None, Cow::Borrowed(num_str)));
+    }
+
+    let mut currency = None;
+    let mut amount = String::with_capacity(num_str.len());
+    let mut rest = num_str;
+    while let Some(c) = rest.chars().next() {
+        // Codes have to be whole words, so `e` in `1e5` is left alone
+        let len = if c.is_ascii_alphabetic() {
+            rest.find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        let (word, after) = rest.split_at(len);
+
+        match (currency_of(word), currency) {
+            (Some(code), Some(other)) if code != other => {
+                return Err(format!(
+                    "both {} and {} are given as its currency",
+                    other, code
+                ));
+            }
+            (Some(code), _) => currency = Some(code),
+            (None, _) => amount.push_str(word),
+        }
+        rest = after;
+    }
+
+    if currency.is_none() {
+        return Ok((None, Cow::Borrowed(num_str)));
+    }
+
+    // Drop the space that separated the currency from the rest, as in `USD 12`
+    let amount = amount.split(' ').filter(|part| !part.is_empty());
+    Ok((currency, Cow::Owned(amount.collect::<Vec<_>>().join(" "))))
+}
+
+fn is_currency_symbol(c: char) -> bool {
+    SYMBOLS.iter().any(|&(symbol, _)| symbol == c)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splits_symbols_and_codes_off_amounts() {
+        for (num_str, expected) in [
+            ("$1,200.50", (Some("USD"), "1,200.50")),
+            ("€30", (Some("EUR"), "30")),
+            ("12.00 USD", (Some("USD"), "12.00")),
+            ("JPY 5000", (Some("JPY"), "5000")),
+            ("-£3", (Some("GBP"), "-3")),
+            ("($12)", (Some("USD"), "(12)")),
+            ("$12 USD", (Some("USD"), "12")),
+            ("12 € CR", (Some("EUR"), "12 CR")),
+            ("1e5", (None, "1e5")),
+            ("12", (None, "12")),
+            ("USDX", (None, "USDX")),
+        ] {
+            let (currency, amount) = split_currency(num_str).unwrap();
+            assert_eq!((currency, amount.as_ref()), expected, "{}", num_str);
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_in_two_currencies() {
+        assert_eq!(
+            split_currency("$12 EUR"),
+            Err("both USD and EUR are given as its currency".to_owned())
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::{cmp::Ordering, fmt, iter::Sum, ops::AddAssign, str::FromStr};
+
+use crate::{integer::Integer, precision::Rounding, rational::Rational};
+
+/// Exponents beyond this are rejected so a token like `1e999999999` can't exhaust memory.
+const MAX_EXPONENT: i64 = 9_999;
+
+/// Exact decimal number with an unbounded number of digits on both sides of the point.
+///
+/// The value is `unscaled * 10^-scale`, where the unscaled [`Integer`] stays an `i128` until it
+/// overflows, so inputs that are all integers are summed without any big-number arithmetic.
+///
+/// The scale is kept as written in the input (`1.50` has a scale of 2), and sums take the larger
+/// scale of their operands so the total is printed with the same number of decimal places as the
+/// most precise input. Integer inputs have a scale of 0 and therefore print as exact integers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Decimal {
+    unscaled: Integer,
+    scale: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl Decimal {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn unscaled(&self) -> &Integer {
+        &self.unscaled
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Exact value of `n`, or `None` if it has infinitely many decimal places, as `1/3` does.
+    pub fn from_rational(n: &Rational) -> Option<Self> {
+        // A fraction in lowest terms ends iff its denominator is 2^a * 5^b, after max(a, b) places
+        let mut rest = n.denom().clone();
+        let mut counts = [0, 0];
+        for (factor, count) in [2, 5].into_iter().zip(&mut counts) {
+            loop {
+                let (quotient, remainder) = rest.div_rem(&Integer::Small(factor));
+                if !remainder.is_zero() {
+                    break;
+                }
+                rest = quotient;
+                *count += 1;
+            }
+        }
+        if rest != Integer::Small(1) {
+            return None;
+        }
+
+        let scale = counts[0].max(counts[1]);
+        Some(Self {
+            unscaled: n.numer().mul_pow10(scale).div_rem(n.denom()).0,
+            scale,
+        })
+    }
+
+    /// Product with `factor`, rounded to `scale` decimal places with halves rounded away from
+    /// zero.
+    pub fn scaled_by(&self, factor: &Rational, scale: u32) -> Self {
+        let num = (&self.unscaled * factor.numer()).mul_pow10(scale);
+        let den = factor.denom().mul_pow10(self.scale);
+        Self {
+            unscaled: num.div_round(&den),
+            scale,
+        }
+    }
+
+    /// `n` rounded to `scale` decimal places in the way of `rounding`.
+    pub fn round(n: &Rational, scale: u32, rounding: Rounding) -> Self {
+        let (quotient, remainder) = n.numer().mul_pow10(scale).div_rem(n.denom());
+        let negative = n.numer().is_negative() != n.denom().is_negative();
+
+        // The quotient is truncated, so rounding either keeps it or moves it away from zero
+        let away = !remainder.is_zero()
+            && match rounding {
+                Rounding::Truncate => false,
+                Rounding::Floor => negative,
+                Rounding::Ceil => !negative,
+                Rounding::HalfUp | Rounding::HalfEven => {
+                    let mut excess = &remainder.abs() * &Integer::Small(2);
+                    excess += &-n.denom().abs();
+                    match (excess.is_zero(), rounding) {
+                        (true, Rounding::HalfEven) => {
+                            !quotient.div_rem(&Integer::Small(2)).1.is_zero()
+                        }
+                        (true, _) => true,
+                        (false, _) => !excess.is_negative(),
+                    }
+                }
+            };
+
+        let mut unscaled = quotient;
+        if away {
+            unscaled += &Integer::Small(if negative { -1 } else { 1 });
+        }
+        Self { unscaled, scale }
+    }
+
+    /// Square root rounded to `scale` decimal places in the way of `rounding`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is negative.
+    pub fn sqrt(&self, scale: u32, rounding: Rounding) -> Self {
+        // The root is that of `num / 10^shift` units of the last place
+        let (num, shift) = match (2 * scale).checked_sub(self.scale) {
+            Some(exp) => (self.unscaled.mul_pow10(exp), 0),
+            None => (self.unscaled.clone(), self.scale - 2 * scale),
+        };
+        let root = num.div_rem(&Integer::Small(1).mul_pow10(shift)).0.sqrt();
+
+        // The root rounded down either is the root or is one below rounding away from it
+        let exact = (&root * &root).mul_pow10(shift) == num;
+        let away = !exact
+            && match rounding {
+                Rounding::Truncate | Rounding::Floor => false,
+                Rounding::Ceil => true,
+                Rounding::HalfUp | Rounding::HalfEven => {
+                    // Compares the square of the root to that of the midpoint, `root + 1/2`
+                    let mut midpoint = &root * &Integer::Small(2);
+                    midpoint += &Integer::Small(1);
+                    let midpoint_square = (&midpoint * &midpoint).mul_pow10(shift);
+                    match (&num * &Integer::Small(4)).cmp(&midpoint_square) {
+                        Ordering::Less => false,
+                        Ordering::Greater => true,
+                        Ordering::Equal => {
+                            rounding == Rounding::HalfUp
+                                || !root.div_rem(&Integer::Small(2)).1.is_zero()
+                        }
+                    }
+                }
+            };
+
+        let mut unscaled = root;
+        if away {
+            unscaled += &Integer::Small(1);
+        }
+        Self { unscaled, scale }
+    }
+
+    fn rescaled(&self, scale: u32) -> Integer {
+        self.unscaled.mul_pow10(scale - self.scale)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    /// Parses `[+-]digits[.digits][(e|E)[+-]digits]`, requiring at least one mantissa digit.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], parse_exponent(&s[i + 1..])?),
+            None => (s, 0),
+        };
+
+        let (int_digits, frac_digits) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if int_digits.len() + frac_digits.len() == 0
+            || !is_digits(int_digits)
+            || !is_digits(frac_digits)
+        {
+            return Err(ParseDecimalError);
+        }
+
+        let digits = [int_digits.as_bytes(), frac_digits.as_bytes()].concat();
+        let mut unscaled = Integer::from_decimal_digits(&digits);
+        if negative {
+            unscaled = -unscaled;
+        }
+
+        let scale = frac_digits.len() as i64 - exponent;
+        if scale >= 0 {
+            Ok(Self {
+                unscaled,
+                scale: scale as u32,
+            })
+        } else {
+            Ok(Self {
+                unscaled: unscaled.mul_pow10(-scale as u32),
+                scale: 0,
+            })
+        }
+    }
+}
+
+fn parse_exponent(s: &str) -> Result<i64, ParseDecimalError> {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ParseDecimalError);
+    }
+
+    match s.parse::<i64>() {
+        Ok(exp) if exp.abs() <= MAX_EXPONENT => Ok(exp),
+        _ => Err(ParseDecimalError),
+    }
+}
+
+impl AddAssign<&Decimal> for Decimal {
+    fn add_assign(&mut self, rhs: &Decimal) {
+        if self.scale < rhs.scale {
+            self.unscaled = self.rescaled(rhs.scale);
+            self.scale = rhs.scale;
+        }
+
+        if self.scale == rhs.scale {
+            self.unscaled += &rhs.unscaled;
+        } else {
+            self.unscaled += &rhs.rescaled(self.scale);
+        }
+    }
+}
+
+impl Sum for Decimal {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |mut acc, n| {
+            acc += &n;
+            acc
+        })
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.unscaled.to_string();
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", digits.as_str()),
+        };
+
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, int_part, frac_part)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn can_parse_and_display_decimals() {
+        for s in [
+            "0",
+            "-12",
+            "0.1",
+            "-0.005",
+            "123456789012345678901234567890.123456789",
+        ] {
+            assert_eq!(dec(s).to_string(), s);
+        }
+        assert_eq!(dec("40.").to_string(), "40");
+        assert_eq!(dec(".5").to_string(), "0.5");
+        assert_eq!(dec("+1.5e-3").to_string(), "0.0015");
+        assert_eq!(dec("6.02E23").to_string(), "602000000000000000000000");
+    }
+
+    #[test]
+    fn rejects_malformed_decimals() {
+        for s in ["", ".", "-", "1.2.3", "1e", "1e+", "abc", "1e99999", "١٢"] {
+            assert_eq!(s.parse::<Decimal>(), Err(ParseDecimalError), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn sums_exactly_with_largest_scale() {
+        let sum = ["1000.25", "250.25", "125.25", "0.25"]
+            .into_iter()
+            .map(dec)
+            .sum::<Decimal>();
+        assert_eq!(sum.to_string(), "1376.00");
+
+        let sum = ["0.1", "0.2", "-0.30"]
+            .into_iter()
+            .map(dec)
+            .sum::<Decimal>();
+        assert_eq!(sum.to_string(), "0.00");
+    }
+
+    #[test]
+    fn scales_and_rounds_to_a_given_scale() {
+        let rate = "1/1.08".parse::<Rational>().unwrap();
+        assert_eq!(dec("30").scaled_by(&rate, 2).to_string(), "27.78");
+        assert_eq!(
+            dec("-0.125")
+                .scaled_by(&"2".parse().unwrap(), 1)
+                .to_string(),
+            "-0.3"
+        );
+    }
+
+    #[test]
+    fn rounds_in_every_rounding_mode() {
+        let round = |s: &str, scale, rounding| {
+            Decimal::round(&s.parse().unwrap(), scale, rounding).to_string()
+        };
+
+        for (s, expected) in [
+            ("0.125", ["0.12", "0.13", "0.12", "0.13", "0.12"]),
+            ("0.135", ["0.14", "0.14", "0.13", "0.14", "0.13"]),
+            ("-0.125", ["-0.12", "-0.13", "-0.13", "-0.12", "-0.12"]),
+            (
+                "1375.9999",
+                ["1376.00", "1376.00", "1375.99", "1376.00", "1375.99"],
+            ),
+            ("2/3", ["0.67", "0.67", "0.66", "0.67", "0.66"]),
+            ("-0.001", ["0.00", "0.00", "-0.01", "0.00", "0.00"]),
+            ("7", ["7.00", "7.00", "7.00", "7.00", "7.00"]),
+        ] {
+            let rounded = [
+                Rounding::HalfEven,
+                Rounding::HalfUp,
+                Rounding::Floor,
+                Rounding::Ceil,
+                Rounding::Truncate,
+            ]
+            .map(|rounding| round(s, 2, rounding));
+            assert_eq!(rounded, expected, "{}", s);
+        }
+        assert_eq!(round("1234.5", 0, Rounding::HalfEven), "1234");
+    }
+
+    #[test]
+    fn takes_square_roots_to_a_given_scale() {
+        let sqrt = |s: &str, scale, rounding| dec(s).sqrt(scale, rounding).to_string();
+
+        assert_eq!(sqrt("0.25", 2, Rounding::HalfEven), "0.50");
+        assert_eq!(sqrt("2", 3, Rounding::HalfEven), "1.414");
+        assert_eq!(sqrt("2", 3, Rounding::Ceil), "1.415");
+        assert_eq!(sqrt("0.0003", 1, Rounding::HalfUp), "0.0");
+        // Exactly halfway, as the root of 2.25 is 1.5
+        assert_eq!(sqrt("2.25", 0, Rounding::HalfEven), "2");
+        assert_eq!(sqrt("6.25", 0, Rounding::HalfEven), "2");
+        assert_eq!(sqrt("2.25", 0, Rounding::HalfUp), "2");
+        assert_eq!(
+            sqrt("1e400", 1, Rounding::HalfEven),
+            format!("1{}.0", "0".repeat(200))
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use std::{fmt, ops::AddAssign, str::FromStr};
+
+use crate::{decimal::Decimal, integer::Integer, rational::Rational};
+
+/// Units of Go style durations such as `1h30m15s`, as seconds and powers of ten of a second.
+const UNITS: &[(&str, i128, u32)] = &[
+    ("h", 3600, 0),
+    ("m", 60, 0),
+    ("s", 1, 0),
+    ("ms", 1, 3),
+    ("us", 1, 6),
+    ("µs", 1, 6),
+    ("ns", 1, 9),
+];
+
+/// Designators of ISO 8601 durations before and after the `T`, in the order they are written.
+const ISO_DATE_UNITS: &[(&str, i128, u32)] = &[("W", 604_800, 0), ("D", 86_400, 0)];
+const ISO_TIME_UNITS: &[(&str, i128, u32)] = &[("H", 3600, 0), ("M", 60, 0), ("S", 1, 0)];
+
+/// Exact length of time, summed by the duration mode.
+///
+/// It is held as a [`Decimal`] number of seconds, so fractions of a second such as the `.25` of
+/// `1:30:15.25` or `1.5ms` are summed without rounding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Duration {
+    seconds: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDurationError;
+
+/// How the duration mode prints its total.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DurationFormat {
+    /// Hours, minutes and seconds, as in `1:30:15`.
+    #[default]
+    Clock,
+    /// Go style, as in `1h30m15s`.
+    Go,
+    /// ISO 8601, as in `PT1H30M15S`.
+    Iso,
+    /// A number of seconds, as in `5415`.
+    Seconds,
+}
+
+impl FromStr for DurationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clock" => Ok(Self::Clock),
+            "go" => Ok(Self::Go),
+            "iso" => Ok(Self::Iso),
+            "seconds" => Ok(Self::Seconds),
+            _ => Err(format!(
+                "Unknown duration format '{}'. Expected one of: clock, go, iso, seconds.",
+                s
+            )),
+        }
+    }
+}
+
+impl Duration {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn from_seconds(seconds: Decimal) -> Self {
+        Self { seconds }
+    }
+
+    pub fn seconds(&self) -> &Decimal {
+        &self.seconds
+    }
+
+    pub fn format(&self, format: DurationFormat) -> String {
+        if format == DurationFormat::Seconds {
+            return self.seconds.to_string();
+        }
+
+        // Split into whole hours, minutes and seconds, and the fraction of a second
+        let unscaled = self.seconds.unscaled();
+        let sign = if unscaled.is_negative() { "-" } else { "" };
+        let one = Integer::Small(1).mul_pow10(self.seconds.scale());
+        let (whole, fraction) = unscaled.abs().div_rem(&one);
+        let (hours, rest) = whole.div_rem(&Integer::Small(3600));
+        let (minutes, seconds) = rest.div_rem(&Integer::Small(60));
+        let has_seconds = !seconds.is_zero() || !fraction.is_zero();
+        let fraction = match self.seconds.scale() {
+            0 => String::new(),
+            scale => format!(".{:0>width$}", fraction.to_string(), width = scale as usize),
+        };
+
+        match format {
+            DurationFormat::Clock => format!(
+                "{}{}:{:0>2}:{:0>2}{}",
+                sign,
+                hours,
+                minutes.to_string(),
+                seconds.to_string(),
+                fraction
+            ),
+            DurationFormat::Go if !hours.is_zero() => {
+                format!("{}{}h{}m{}{}s", sign, hours, minutes, seconds, fraction)
+            }
+            DurationFormat::Go if !minutes.is_zero() => {
+                format!("{}{}m{}{}s", sign, minutes, seconds, fraction)
+            }
+            DurationFormat::Go => format!("{}{}{}s", sign, seconds, fraction),
+            DurationFormat::Iso => {
+                let mut iso = format!("{}PT", sign);
+                if !hours.is_zero() {
+                    iso += &format!("{}H", hours);
+                }
+                if !minutes.is_zero() {
+                    iso += &format!("{}M", minutes);
+                }
+                if has_seconds || iso.ends_with('T') {
+                    iso += &format!("{}{}S", seconds, fraction);
+                }
+                iso
+            }
+            DurationFormat::Seconds => unreachable!("printed above"),
+        }
+    }
+}
+
+impl FromStr for Duration {
+    type Err = ParseDurationError;
+
+    /// Parses an optional sign followed by a clock time (`1:30` or `1:30:15.5`), a Go style
+    /// duration (`1h30m15s`), an ISO 8601 duration (`PT1H30M`, `P1DT2H`) or a number of seconds.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, unsigned) = match s.strip_prefix(['-', '+']) {
+            Some(unsigned) => (s.starts_with('-'), unsigned),
+            None => (false, s),
+        };
+
+        let seconds = if let Some(iso) = unsigned.strip_prefix('P') {
+            parse_iso(iso)?
+        } else if unsigned.contains(':') {
+            parse_clock(unsigned)?
+        } else if unsigned.ends_with(|c: char| c.is_alphabetic()) {
+            parse_units(unsigned, UNITS, false)?
+        } else {
+            component(unsigned)?
+        };
+
+        let seconds = if negative {
+            &seconds * &Rational::from(Integer::Small(-1))
+        } else {
+            seconds
+        };
+        let seconds = Decimal::from_rational(&seconds)
+            .expect("units are whole seconds or powers of ten of a second");
+        Ok(Self { seconds })
+    }
+}
+
+/// Seconds in `1:30` (hours and minutes) or `1:30:15.5`.
+fn parse_clock(s: &str) -> Result<Rational, ParseDurationError> {
+    let (hours, minutes, seconds) = match s.split(':').collect::<Vec<_>>()[..] {
+        [hours, minutes] => (hours, minutes, None),
+        [hours, minutes, seconds] => (hours, minutes, Some(seconds)),
+        _ => return Err(ParseDurationError),
+    };
+
+    // Minutes and whole seconds are two digits below 60
+    let below_sixty = |s: &str| s.len() == 2 && s.bytes().all(|b| b.is_ascii_digit()) && s < "60";
+    let whole_seconds = seconds.map(|s| s.split_once('.').map_or(s, |(whole, _)| whole));
+    if hours.contains('.')
+        || !below_sixty(minutes)
+        || whole_seconds.is_some_and(|s| !below_sixty(s))
+    {
+        return Err(ParseDurationError);
+    }
+
+    let mut total = &component(hours)? * &Rational::from(Integer::Small(3600));
+    total += &(&component(minutes)? * &Rational::from(Integer::Small(60)));
+    if let Some(seconds) = seconds {
+        total += &component(seconds)?;
+    }
+    Ok(total)
+}
+
+/// Seconds in the part of an ISO 8601 duration after the `P`, such as `T1H30M` or `1DT2H`.
+/// Years and months are rejected since their length varies.
+fn parse_iso(s: &str) -> Result<Rational, ParseDurationError> {
+    let (date, time) = match s.split_once('T') {
+        Some((_, "")) => return Err(ParseDurationError),
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return Err(ParseDurationError);
+    }
+
+    let mut total = parse_units(date, ISO_DATE_UNITS, true)?;
+    if let Some(time) = time {
+        total += &parse_units(time, ISO_TIME_UNITS, true)?;
+    }
+    Ok(total)
+}
+
+/// Seconds in a sequence of numbers followed by units, such as `1h30m`. If `ordered` is set,
+/// units have to be given in the order of `units`, each at most once.
+fn parse_units(
+    mut s: &str,
+    units: &[(&str, i128, u32)],
+    ordered: bool,
+) -> Result<Rational, ParseDurationError> {
+    let mut total = Rational::zero();
+    let mut next_unit = 0;
+    while !s.is_empty() {
+        let unit_start = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or(ParseDurationError)?;
+        let unit_end = s[unit_start..]
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .map_or(s.len(), |i| unit_start + i);
+        let (number, unit) = (&s[..unit_start], &s[unit_start..unit_end]);
+
+        let i = units
+            .iter()
+            .position(|&(name, _, _)| name == unit)
+            .ok_or(ParseDurationError)?;
+        if ordered && i < next_unit {
+            return Err(ParseDurationError);
+        }
+        next_unit = i + 1;
+
+        let (_, seconds, places) = units[i];
+        let unit = Rational::new(Integer::Small(seconds), Integer::Small(1).mul_pow10(places))
+            .expect("a power of ten is never zero");
+        total += &(&component(number)? * &unit);
+        s = &s[unit_end..];
+    }
+    Ok(total)
+}
+
+/// Value of a number of units without a sign, such as the `1.5` of `1.5h`.
+fn component(s: &str) -> Result<Rational, ParseDurationError> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.len() + fraction.len() == 0 || !is_digits(whole) || !is_digits(fraction) {
+        return Err(ParseDurationError);
+    }
+    s.parse().map_err(|_| ParseDurationError)
+}
+
+impl AddAssign<&Duration> for Duration {
+    fn add_assign(&mut self, rhs: &Duration) {
+        self.seconds += &rhs.seconds;
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(DurationFormat::default()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn seconds(s: &str) -> Option<String> {
+        s.parse::<Duration>().ok().map(|d| d.seconds.to_string())
+    }
+
+    #[test]
+    fn parses_clock_go_and_iso_durations() {
+        for (s, expected) in [
+            ("00:45:10", Some("2710")),
+            ("1:30", Some("5400")),
+            ("-0:00:01.25", Some("-1.25")),
+            ("1h30m15s", Some("5415")),
+            ("90s", Some("90")),
+            ("1.5h", Some("5400")),
+            ("1500ms", Some("1.5")),
+            ("2µs", Some("0.000002")),
+            ("PT1H30M", Some("5400")),
+            ("P1DT2H", Some("93600")),
+            ("P1W", Some("604800")),
+            ("PT0.5S", Some("0.5")),
+            ("42", Some("42")),
+            ("1:60", None),
+            ("1:5", None),
+            ("1h30", None),
+            ("1x", None),
+            ("P1Y", None),
+            ("PT1M1H", None),
+            ("PT", None),
+            ("P", None),
+            ("--1s", None),
+        ] {
+            assert_eq!(seconds(s).as_deref(), expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn formats_durations() {
+        let duration = |s: &str| s.parse::<Duration>().unwrap();
+
+        for (s, format, expected) in [
+            ("5415", DurationFormat::Clock, "1:30:15"),
+            ("62.5", DurationFormat::Clock, "0:01:02.5"),
+            ("-5.25", DurationFormat::Clock, "-0:00:05.25"),
+            ("36000", DurationFormat::Clock, "10:00:00"),
+            ("5415", DurationFormat::Go, "1h30m15s"),
+            ("3600", DurationFormat::Go, "1h0m0s"),
+            ("0.5", DurationFormat::Go, "0.5s"),
+            ("5415", DurationFormat::Iso, "PT1H30M15S"),
+            ("5400", DurationFormat::Iso, "PT1H30M"),
+            ("0", DurationFormat::Iso, "PT0S"),
+            ("-90", DurationFormat::Iso, "-PT1M30S"),
+            ("1h", DurationFormat::Seconds, "3600"),
+        ] {
+            assert_eq!(duration(s).format(format), expected, "{}", s);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+
+/// Exit codes, following the BSD `sysexits.h` conventions.
+const EXIT_USAGE: i32 = 64;
+const EXIT_DATA: i32 = 65;
+const EXIT_IO: i32 = 74;
+
+/// Error that ends the program, grouped by the exit code it is reported with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Invalid command line arguments.
+    Usage(String),
+    /// Input that couldn't be read.
+    Io(String),
+    /// Input that was read but isn't valid.
+    Parse(ParseErrors),
+    /// Input whose numbers are valid, but can't be summed together.
+    Data(String),
+}
+
+impl Error {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Usage(_) => EXIT_USAGE,
+            Self::Parse(_) | Self::Data(_) => EXIT_DATA,
+            Self::Io(_) => EXIT_IO,
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Self::Parse(ParseErrors {
+            errors: vec![e],
+            limit: 1,
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) | Self::Io(message) | Self::Data(message) => {
+                write!(f, "{}", message)
+            }
+            Self::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Tokens that failed to parse, in input order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseErrors {
+    pub errors: Vec<ParseError>,
+    /// Number of errors after which parsing stops.
+    pub limit: usize,
+}
+
+impl ParseErrors {
+    /// Whether parsing stopped at the limit, so the input may contain more errors.
+    pub fn reached_limit(&self) -> bool {
+        self.errors.len() >= self.limit
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+            write!(f, "{}", e)?;
+        }
+
+        // A lone error is the default of stopping at the first one, which needs no summary
+        if self.limit > 1 {
+            if self.reached_limit() {
+                write!(
+                    f,
+                    "\n\nStopped after {} numbers failed to parse.",
+                    self.errors.len()
+                )?;
+            } else {
+                write!(f, "\n\n{} numbers failed to parse.", self.errors.len())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tokens that failed to parse but were skipped instead of ending the sum.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    pub count: usize,
+    /// The skipped tokens in input order, if they are being listed.
+    pub tokens: Vec<ParseError>,
+    /// Number of infinities and NaNs left out by `--non-finite skip`.
+    pub non_finite: usize,
+}
+
+impl Skipped {
+    pub fn append(&mut self, other: &mut Self) {
+        self.count += other.count;
+        self.tokens.append(&mut other.tokens);
+        self.non_finite += other.non_finite;
+    }
+}
+
+/// Token that failed to parse, along with where it is in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    /// Name of the input, such as a file path.
+    pub source: String,
+    pub line: usize,
+    pub column: usize,
+    /// The token as it appears in the input.
+    pub token: String,
+    /// The full line the token is on, if it could be kept.
+    pub line_text: Option<String>,
+}
+
+impl fmt::Display for ParseError {
+    /// Formats the error with the offending token underlined in its line:
+    ///
+    /// ```text
+    /// Failed to parse '1.2.3'
+    ///  --> numbers.txt:2:3
+    ///   |
+    /// 2 | 4 1.2.3 5
+    ///   |   ^^^^^
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+
+        let gutter = " ".repeat(self.line.to_string().len());
+        write!(
+            f,
+            "\n{}--> {}:{}:{}",
+            gutter, self.source, self.line, self.column
+        )?;
+
+        let Some(line_text) = &self.line_text else {
+            return Ok(());
+        };
+
+        // Tabs are kept in the padding so the carets line up however wide they are displayed
+        let padding = line_text
+            .chars()
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let carets = "^".repeat(self.token.chars().count().max(1));
+
+        write!(
+            f,
+            "\n{} |\n{} | {}\n{} | {}{}",
+            gutter, self.line, line_text, gutter, padding, carets
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_error(line_text: Option<&str>) -> ParseError {
+        ParseError {
+            message: "Failed to parse '1.2.3'".to_owned(),
+            source: "numbers.txt".to_owned(),
+            line: 12,
+            column: 5,
+            token: "1.2.3".to_owned(),
+            line_text: line_text.map(str::to_owned),
+        }
+    }
+
+    #[test]
+    fn underlines_the_token_in_its_line() {
+        let expected = "Failed to parse '1.2.3'
+  --> numbers.txt:12:5
+   |
+12 | 4\t1 1.2.3 5
+   |  \t  ^^^^^";
+
+        assert_eq!(parse_error(Some("4\t1 1.2.3 5")).to_string(), expected);
+    }
+
+    #[test]
+    fn shows_only_the_location_without_the_line() {
+        let expected = "Failed to parse '1.2.3'\n  --> numbers.txt:12:5";
+
+        assert_eq!(parse_error(None).to_string(), expected);
+    }
+
+    #[test]
+    fn summarizes_collected_errors() {
+        let mut errors = ParseErrors {
+            errors: vec![parse_error(None), parse_error(None)],
+            limit: 3,
+        };
+        let error = "Failed to parse '1.2.3'\n  --> numbers.txt:12:5";
+
+        assert_eq!(
+            errors.to_string(),
+            format!("{}\n\n{}\n\n2 numbers failed to parse.", error, error)
+        );
+
+        errors.limit = 2;
+        assert!(errors
+            .to_string()
+            .ends_with("Stopped after 2 numbers failed to parse."));
+    }
+}
@@ -0,0 +1,506 @@
+use std::{borrow::Cow, env};
+
+/// Characters that group digits in locales writing numbers as `1 234,5`: the narrow no-break
+/// space, the no-break space and the thin space. A plain space separates numbers instead.
+const SPACE_GROUPING: [char; 3] = ['\u{202F}', '\u{A0}', '\u{2009}'];
+
+/// Languages writing numbers as `1.234,5`.
+const COMMA_DOT_LANGUAGES: [&str; 19] = [
+    "de", "es", "it", "nl", "pt", "da", "id", "tr", "el", "ro", "hr", "sl", "sr", "vi", "is", "ca",
+    "gl", "eu", "az",
+];
+
+/// Languages writing numbers as `1 234,5`.
+const COMMA_SPACE_LANGUAGES: [&str; 16] = [
+    "fr", "ru", "uk", "be", "pl", "cs", "sk", "fi", "sv", "nb", "nn", "no", "hu", "bg", "lt", "lv",
+];
+
+/// Languages writing numbers as `1,234.5`.
+const DOT_COMMA_LANGUAGES: [&str; 14] = [
+    "en", "ja", "zh", "ko", "th", "he", "hi", "bn", "ta", "te", "mr", "ms", "ga", "cy",
+];
+
+/// How numbers write their decimal point and group their digits.
+///
+/// Numbers are normalized to use `.` as the decimal point and no grouping before they are
+/// parsed, so every numeric type accepts every format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal: char,
+    /// Characters allowed between digits, which are ignored.
+    pub grouping: Vec<char>,
+    /// Number of digits in the group before the decimal point, and in the groups before it.
+    pub group_sizes: (usize, usize),
+    /// Whether grouping is only allowed between groups of [`NumberFormat::group_sizes`] digits
+    /// in the integer part, instead of anywhere.
+    pub strict: bool,
+    /// Whether a number whose only separator is a `.` or `,` before three digits, as in `1.500`
+    /// or `1,500`, is an error, as locales disagree on whether it is grouping or a decimal point.
+    pub unambiguous: bool,
+    /// Whether negatives can be written in accounting notation, as `(12)`, `12-`, `−12` with a
+    /// Unicode minus, or `12 CR`.
+    pub accounting: bool,
+    /// Whether a whole number and a fraction written apart, as in `1 1/2`, are one number.
+    pub mixed_numbers: bool,
+    /// Whether percentages are read as percentage points (`15%` as `15`) instead of as
+    /// fractions of one (`0.15`).
+    pub percent_points: bool,
+    /// Whether amounts can have a size suffix, as `1.5K` or `200MiB` do, and are read as a
+    /// number of bytes.
+    pub sizes: bool,
+    /// Whether amounts can have a unit of measure, as `3.2kg` or `12 km` do.
+    pub units: bool,
+    /// Whether numbers can be written in English words, as `twelve`, `one hundred and five` or
+    /// `3 million`.
+    pub words: bool,
+    /// Whether amounts can be given with an uncertainty, as `1.5±0.1` or `1.5 +/- 0.1` are.
+    pub uncertainties: bool,
+}
+
+impl Default for NumberFormat {
+    /// `1,234.5`, the format of the `C` locale with commas allowed for grouping.
+    fn default() -> Self {
+        Self {
+            decimal: '.',
+            grouping: vec![','],
+            group_sizes: (3, 3),
+            strict: false,
+            unambiguous: false,
+            accounting: false,
+            mixed_numbers: false,
+            percent_points: false,
+            sizes: false,
+            units: false,
+            words: false,
+            uncertainties: false,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Format of a POSIX locale name such as `de_DE.UTF-8`, if the language is known.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let (language, territory) = locale.split_once(['_', '-']).unwrap_or((locale, ""));
+
+        let (decimal, grouping) = match (language, territory) {
+            ("C" | "POSIX", "") => return Some(Self::default()),
+            // Swiss and Liechtenstein formats group with apostrophes whatever the language
+            (_, "CH" | "LI") => ('.', vec!['\'', '’']),
+            ("es", "MX" | "US") => ('.', vec![',']),
+            _ if COMMA_DOT_LANGUAGES.contains(&language) => (',', vec!['.']),
+            _ if COMMA_SPACE_LANGUAGES.contains(&language) => (',', SPACE_GROUPING.to_vec()),
+            _ if DOT_COMMA_LANGUAGES.contains(&language) => ('.', vec![',']),
+            _ => return None,
+        };
+
+        // Indian formats group the digits before the last three in pairs, as in `1,00,000`
+        let group_sizes = if territory == "IN" { (3, 2) } else { (3, 3) };
+
+        Some(Self {
+            decimal,
+            grouping,
+            group_sizes,
+            ..Self::default()
+        })
+    }
+
+    /// Format of the locale numbers are formatted in according to `LC_ALL`, `LC_NUMERIC` and
+    /// `LANG`, in that order of precedence, or the default if none is set. Fails with the reason
+    /// for unknown locales.
+    ///
+    /// As the locale is easily set for other reasons than reading numbers, its grouping is
+    /// strict, and numbers that would be read differently in the default format are errors if
+    /// their separators don't tell which they are.
+    pub fn from_env() -> Result<Self, String> {
+        let Some((var, locale)) = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|var| Some((var, env::var(var).ok()?)))
+            .find(|(_, locale)| !locale.is_empty())
+        else {
+            return Ok(Self::default());
+        };
+
+        let format = Self::from_locale(&locale)
+            .ok_or_else(|| format!("Unknown locale '{}' in {}.", locale, var))?;
+        Ok(Self {
+            strict: true,
+            unambiguous: format.decimal != '.',
+            ..format
+        })
+    }
+
+    /// Uses `decimal` as the decimal point. If it was used for grouping, the previous decimal
+    /// point takes its place, so that `--decimal ,` turns `1,234.5` into `1.234,5`.
+    pub fn with_decimal(mut self, decimal: char) -> Self {
+        if decimal != self.decimal {
+            for c in &mut self.grouping {
+                if *c == decimal {
+                    *c = self.decimal;
+                }
+            }
+            self.decimal = decimal;
+        }
+        self
+    }
+
+    /// Rewrites `num_str` with `.` as the decimal point, without grouping characters, and with
+    /// accounting negatives as plain ones.
+    ///
+    /// Fails with the reason if it contains a `.` that is neither the decimal point nor
+    /// grouping, as it would otherwise be mistaken for one, or if grouping is strict and
+    /// misplaced.
+    pub fn normalize<'a>(&self, num_str: &'a str) -> Result<Cow<'a, str>, String> {
+        if !self.accounting {
+            return self.normalize_separators(num_str);
+        }
+
+        let (negative, magnitude) = split_accounting_sign(num_str);
+        let magnitude = magnitude.replace('\u{2212}', "-");
+        let normalized = self.normalize_separators(&magnitude)?;
+        if !negative {
+            return Ok(Cow::Owned(normalized.into_owned()));
+        }
+
+        if normalized.starts_with(['-', '+']) {
+            return Err("a negative in accounting notation can't have a sign".to_owned());
+        }
+        Ok(Cow::Owned(format!("-{}", normalized)))
+    }
+
+    fn normalize_separators<'a>(&self, num_str: &'a str) -> Result<Cow<'a, str>, String> {
+        if num_str.contains('_') {
+            let without_underscores = remove_underscores(num_str)?;
+            let normalized = self.normalize_separators(&without_underscores)?;
+            return Ok(Cow::Owned(normalized.into_owned()));
+        }
+
+        let needs_rewrite = |c: char| {
+            self.grouping.contains(&c) || (self.decimal != '.' && (c == self.decimal || c == '.'))
+        };
+        if !num_str.contains(needs_rewrite) {
+            return Ok(Cow::Borrowed(num_str));
+        }
+
+        if self.strict {
+            self.check_grouping(num_str)?;
+        }
+        if self.unambiguous {
+            check_unambiguous(num_str)?;
+        }
+
+        let mut normalized = String::with_capacity(num_str.len());
+        for c in num_str.chars() {
+            if c == self.decimal {
+                normalized.push('.');
+            } else if self.grouping.contains(&c) {
+                continue;
+            } else if c == '.' {
+                return Err("'.' is neither the decimal point nor grouping".to_owned());
+            } else {
+                normalized.push(c);
+            }
+        }
+
+        Ok(Cow::Owned(normalized))
+    }
+
+    /// Checks that grouping characters only separate groups of digits of the right size in the
+    /// integer parts of `num_str`, such as both sides of a fraction.
+    fn check_grouping(&self, num_str: &str) -> Result<(), String> {
+        let chars = num_str.chars().collect::<Vec<_>>();
+        let is_digit_or_grouping = |c: char| c.is_ascii_digit() || self.grouping.contains(&c);
+
+        let mut in_integer_part = true;
+        let mut i = 0;
+        while i < chars.len() {
+            if !is_digit_or_grouping(chars[i]) {
+                // Signs keep the part they are in, as in the exponent of `1e-5`
+                match chars[i] {
+                    '/' => in_integer_part = true,
+                    '+' | '-' => {}
+                    _ => in_integer_part = false,
+                }
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < chars.len() && is_digit_or_grouping(chars[i]) {
+                i += 1;
+            }
+            self.check_groups(&chars[start..i], start, in_integer_part)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks the grouping of a run of digits starting at character `offset` of a number.
+    fn check_groups(
+        &self,
+        run: &[char],
+        offset: usize,
+        in_integer_part: bool,
+    ) -> Result<(), String> {
+        let separators = run
+            .iter()
+            .enumerate()
+            .filter(|&(_, c)| self.grouping.contains(c))
+            .map(|(i, &c)| (i, c))
+            .collect::<Vec<_>>();
+        let Some(&(first, c)) = separators.first() else {
+            return Ok(());
+        };
+
+        let misplaced = |(i, c): (usize, char), expected: String| {
+            Err(format!(
+                "grouping '{}' at character {} {}",
+                c,
+                offset + i + 1,
+                expected
+            ))
+        };
+
+        if !in_integer_part {
+            return misplaced((first, c), "is only allowed in the integer part".to_owned());
+        }
+
+        let (primary, secondary) = self.group_sizes;
+        if !(1..=secondary).contains(&first) {
+            return misplaced(
+                (first, c),
+                format!("should follow 1 to {} digits", secondary),
+            );
+        }
+
+        for (n, &(i, c)) in separators.iter().enumerate() {
+            let (size, next) = match separators.get(n + 1) {
+                Some(&(next, _)) => (secondary, next),
+                None => (primary, run.len()),
+            };
+            if next - i - 1 != size {
+                return misplaced((i, c), format!("should be followed by {} digits", size));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that the only separator in `num_str` isn't a `.` or `,` before three digits, which
+/// could be either grouping or a decimal point.
+fn check_unambiguous(num_str: &str) -> Result<(), String> {
+    let mut separators = num_str.match_indices(['.', ',']);
+    let (Some((i, separator)), None) = (separators.next(), separators.next()) else {
+        return Ok(());
+    };
+
+    let after = &num_str[i + 1..];
+    let digits_after = after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits_after == 3 && num_str[..i].ends_with(|c: char| c.is_ascii_digit()) {
+        return Err(format!(
+            "'{}' could be grouping or a decimal point",
+            separator
+        ));
+    }
+    Ok(())
+}
+
+/// Removes Rust-style underscores between digits, as in `1_000_000` or `0xFF_FF`.
+fn remove_underscores(num_str: &str) -> Result<String, String> {
+    let chars = num_str.chars().collect::<Vec<_>>();
+    // Hexadecimal digits included, for the `0xFF_FF` of the hex, octal and binary forms
+    let is_digit = |i: Option<usize>| {
+        i.and_then(|i| chars.get(i))
+            .is_some_and(char::is_ascii_hexdigit)
+    };
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' && !(is_digit(i.checked_sub(1)) && is_digit(Some(i + 1))) {
+            return Err(format!(
+                "'_' at character {} has to be between digits",
+                i + 1
+            ));
+        }
+    }
+
+    Ok(chars.iter().filter(|&&c| c != '_').collect())
+}
+
+/// Whether `word` is the `CR` (credit) or `DR` (debit) suffix of accounting notation, which
+/// can be written apart from the number it applies to.
+pub fn is_accounting_suffix(word: &str) -> bool {
+    word.eq_ignore_ascii_case("CR") || word.eq_ignore_ascii_case("DR")
+}
+
+/// Splits the accounting notation for negatives off `num_str`: parentheses, a trailing minus
+/// or a `CR` suffix. A `DR` suffix is split off as positive.
+fn split_accounting_sign(num_str: &str) -> (bool, &str) {
+    if let Some(inner) = num_str.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        return (true, inner);
+    }
+
+    let suffix_start = num_str.len().saturating_sub(2);
+    if suffix_start > 0 && num_str.is_char_boundary(suffix_start) {
+        let (number, suffix) = num_str.split_at(suffix_start);
+        if is_accounting_suffix(suffix) {
+            return (suffix.eq_ignore_ascii_case("CR"), number.trim_end());
+        }
+    }
+
+    match num_str.strip_suffix(['-', '\u{2212}']) {
+        Some(number) if !number.is_empty() => (true, number),
+        _ => (false, num_str),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn normalize(format: &NumberFormat, num_str: &str) -> Option<String> {
+        format.normalize(num_str).ok().map(Cow::into_owned)
+    }
+
+    #[test]
+    fn can_detect_format_from_locale_name() {
+        let german = NumberFormat::from_locale("de_DE.UTF-8").unwrap();
+        assert_eq!((german.decimal, german.grouping), (',', vec!['.']));
+
+        let swiss = NumberFormat::from_locale("de_CH").unwrap();
+        assert_eq!((swiss.decimal, swiss.grouping), ('.', vec!['\'', '’']));
+
+        let french = NumberFormat::from_locale("fr_FR.UTF-8@euro").unwrap();
+        assert_eq!(french.decimal, ',');
+        assert!(french.grouping.contains(&'\u{202F}'));
+
+        assert_eq!(
+            NumberFormat::from_locale("C"),
+            Some(NumberFormat::default())
+        );
+        assert_eq!(NumberFormat::from_locale("xx_YY"), None);
+    }
+
+    #[test]
+    fn can_normalize_grouped_numbers() {
+        let german = NumberFormat::from_locale("de_DE").unwrap();
+        assert_eq!(
+            normalize(&german, "1.234.567,89"),
+            Some("1234567.89".to_owned())
+        );
+        assert_eq!(normalize(&german, "12,5"), Some("12.5".to_owned()));
+
+        let french = NumberFormat::from_locale("fr_FR").unwrap();
+        assert_eq!(
+            normalize(&french, "1\u{202F}234\u{A0}567,89"),
+            Some("1234567.89".to_owned())
+        );
+        assert_eq!(normalize(&french, "12.5"), None);
+
+        let indian = NumberFormat::from_locale("en_IN").unwrap();
+        assert_eq!(
+            normalize(&indian, "1,00,000.5"),
+            Some("100000.5".to_owned())
+        );
+
+        let swiss = NumberFormat::from_locale("de_CH").unwrap();
+        assert_eq!(normalize(&swiss, "1'234.5"), Some("1234.5".to_owned()));
+    }
+
+    #[test]
+    fn strict_grouping_only_allows_full_groups_in_the_integer_part() {
+        let strict = NumberFormat {
+            strict: true,
+            ..NumberFormat::default()
+        };
+
+        for valid in ["1,234", "12,345,678.9", "-1,000e10", "1,000/3"] {
+            assert!(strict.normalize(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "1,2,3", "1,00", "1234,567", ",123", "1,000,", "1.000,5", "1e1,000",
+        ] {
+            assert!(strict.normalize(invalid).is_err(), "{}", invalid);
+        }
+
+        assert_eq!(
+            strict.normalize("12,34,567"),
+            Err("grouping ',' at character 3 should be followed by 3 digits".to_owned())
+        );
+
+        let indian = NumberFormat {
+            strict: true,
+            ..NumberFormat::from_locale("en_IN").unwrap()
+        };
+        assert!(indian.normalize("12,34,567").is_ok());
+        assert!(indian.normalize("1,234,567").is_err());
+    }
+
+    #[test]
+    fn rejects_ambiguous_separators_when_unambiguous() {
+        let german = NumberFormat {
+            unambiguous: true,
+            ..NumberFormat::from_locale("de_DE").unwrap()
+        };
+
+        for valid in ["1.500,5", "1.500.000", "12,5", "1,5000", "1500"] {
+            assert!(german.normalize(valid).is_ok(), "{}", valid);
+        }
+        assert_eq!(
+            german.normalize("1.500"),
+            Err("'.' could be grouping or a decimal point".to_owned())
+        );
+        assert!(german.normalize("-1,500").is_err());
+    }
+
+    #[test]
+    fn removes_underscores_between_digits() {
+        let format = NumberFormat::default();
+
+        assert_eq!(
+            normalize(&format, "1_000_000.5"),
+            Some("1000000.5".to_owned())
+        );
+        assert_eq!(normalize(&format, "0xFF_FF"), Some("0xFFFF".to_owned()));
+        for num_str in ["_1", "1_", "1__0", "1_.5", "1_x2"] {
+            assert_eq!(normalize(&format, num_str), None, "{}", num_str);
+        }
+    }
+
+    #[test]
+    fn can_normalize_accounting_negatives() {
+        let accounting = NumberFormat {
+            accounting: true,
+            ..NumberFormat::default()
+        };
+
+        for (num_str, expected) in [
+            ("(1,234.56)", Some("-1234.56")),
+            ("1234.56-", Some("-1234.56")),
+            ("\u{2212}12", Some("-12")),
+            ("1,234.56 CR", Some("-1234.56")),
+            ("12cr", Some("-12")),
+            ("12 DR", Some("12")),
+            ("-12", Some("-12")),
+            ("(-12)", None),
+        ] {
+            assert_eq!(
+                normalize(&accounting, num_str).as_deref(),
+                expected,
+                "{}",
+                num_str
+            );
+        }
+
+        assert_eq!(
+            normalize(&NumberFormat::default(), "(12)"),
+            Some("(12)".to_owned())
+        );
+    }
+
+    #[test]
+    fn swaps_grouping_when_changing_the_decimal_point() {
+        let format = NumberFormat::default().with_decimal(',');
+
+        assert_eq!((format.decimal, format.grouping), (',', vec!['.']));
+    }
+}
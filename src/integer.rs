@@ -0,0 +1,445 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{AddAssign, Mul, Neg},
+    str::FromStr,
+};
+
+use crate::bigint::BigInt;
+
+/// Exact integer that is summed as an `i128` and transparently promoted to a [`BigInt`] once an
+/// operation would overflow.
+#[derive(Debug, Clone)]
+pub enum Integer {
+    Small(i128),
+    Big(BigInt),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIntegerError;
+
+impl Integer {
+    pub fn zero() -> Self {
+        Self::Small(0)
+    }
+
+    /// Builds a non-negative integer from a string of ASCII decimal digits.
+    ///
+    /// The caller is responsible for validating the digits.
+    pub fn from_decimal_digits(digits: &[u8]) -> Self {
+        let small = digits.iter().try_fold(0i128, |acc, &d| {
+            acc.checked_mul(10)?.checked_add(i128::from(d - b'0'))
+        });
+
+        match small {
+            Some(n) => Self::Small(n),
+            None => Self::Big(BigInt::from_decimal_digits(digits)),
+        }
+    }
+
+    /// Parses `[+-]digits` in base `radix`, such as `-ff` in base 16.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIntegerError> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if digits.is_empty() {
+            return Err(ParseIntegerError);
+        }
+
+        let base = Self::Small(i128::from(radix));
+        let mut n = Self::zero();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix).ok_or(ParseIntegerError)?;
+            n = &n * &base;
+            n += &Self::Small(i128::from(digit));
+        }
+        Ok(if negative { -n } else { n })
+    }
+
+    /// Formats the integer in base `radix`, with lowercase letters for digits above 9.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        if let Self::Small(n) = self {
+            let magnitude = match radix {
+                16 => format!("{:x}", n.unsigned_abs()),
+                8 => format!("{:o}", n.unsigned_abs()),
+                2 => format!("{:b}", n.unsigned_abs()),
+                _ => n.unsigned_abs().to_string(),
+            };
+            let sign = if *n < 0 { "-" } else { "" };
+            return format!("{}{}", sign, magnitude);
+        }
+
+        let base = Self::Small(i128::from(radix));
+        let mut digits = Vec::new();
+        let mut rest = self.abs();
+        while !rest.is_zero() {
+            let (quotient, digit) = rest.div_rem(&base);
+            let Self::Small(digit) = digit else {
+                unreachable!("a digit is smaller than the radix")
+            };
+            digits.push(char::from_digit(digit as u32, radix).expect("digit below radix"));
+            rest = quotient;
+        }
+        if self.is_negative() {
+            digits.push('-');
+        }
+        digits.iter().rev().collect()
+    }
+
+    /// Returns `self * 10^exp`.
+    pub fn mul_pow10(&self, exp: u32) -> Self {
+        match self {
+            Self::Small(n) => match 10i128.checked_pow(exp).and_then(|p| n.checked_mul(p)) {
+                Some(n) => Self::Small(n),
+                None => Self::Big(BigInt::from_i128(*n).mul_pow10(exp)),
+            },
+            Self::Big(n) => Self::Big(n.mul_pow10(exp)),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Small(n) => *n == 0,
+            Self::Big(n) => n.is_zero(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Self::Small(n) => *n < 0,
+            Self::Big(n) => n.is_negative(),
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        match self {
+            Self::Small(n) => match n.checked_abs() {
+                Some(n) => Self::Small(n),
+                None => Self::Big(BigInt::from_i128(*n).abs()),
+            },
+            Self::Big(n) => Self::Big(n.abs()),
+        }
+    }
+
+    /// Truncating division returning `(quotient, remainder)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Integer) -> (Integer, Integer) {
+        if let (Self::Small(a), Self::Small(b)) = (self, divisor) {
+            if let (Some(q), Some(r)) = (a.checked_div(*b), a.checked_rem(*b)) {
+                return (Self::Small(q), Self::Small(r));
+            }
+        }
+
+        let (q, r) = self.to_big().div_rem(&divisor.to_big());
+        (Self::from_big(q), Self::from_big(r))
+    }
+
+    /// Division rounded to the nearest integer, with halves rounded away from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is zero.
+    pub fn div_round(&self, divisor: &Integer) -> Integer {
+        let (mut quotient, remainder) = self.div_rem(divisor);
+        if remainder.is_zero() {
+            return quotient;
+        }
+
+        // Round away from zero if twice the remainder is at least the divisor
+        let mut excess = &remainder.abs() * &Integer::Small(2);
+        excess += &-divisor.abs();
+        if !excess.is_negative() {
+            let away = if self.is_negative() == divisor.is_negative() {
+                1
+            } else {
+                -1
+            };
+            quotient += &Integer::Small(away);
+        }
+        quotient
+    }
+
+    /// Square root rounded down.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is negative.
+    pub fn sqrt(&self) -> Integer {
+        assert!(!self.is_negative(), "negative integers have no square root");
+        if self.is_zero() {
+            return Self::zero();
+        }
+
+        // Newton's method decreases to the root rounded down from any start above it, such as
+        // the power of ten with half as many digits, rounded up
+        let digits = self.to_string().len() as u32;
+        let mut root = Self::Small(1).mul_pow10(digits.div_ceil(2));
+        loop {
+            let mut next = self.div_rem(&root).0;
+            next += &root;
+            let next = next.div_rem(&Self::Small(2)).0;
+            if next >= root {
+                return root;
+            }
+            root = next;
+        }
+    }
+
+    /// Greatest common divisor, which is always non-negative.
+    pub fn gcd(&self, other: &Integer) -> Integer {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let (_, r) = a.div_rem(&b);
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    /// Demotes a [`BigInt`] back to the `i128` representation when it fits.
+    fn from_big(n: BigInt) -> Self {
+        match n.to_i128() {
+            Some(n) => Self::Small(n),
+            None => Self::Big(n),
+        }
+    }
+
+    fn to_big(&self) -> BigInt {
+        match self {
+            Self::Small(n) => BigInt::from_i128(*n),
+            Self::Big(n) => n.clone(),
+        }
+    }
+
+    fn into_big(self) -> BigInt {
+        match self {
+            Self::Small(n) => BigInt::from_i128(n),
+            Self::Big(n) => n,
+        }
+    }
+}
+
+impl Default for Integer {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl PartialEq for Integer {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Small(a), Self::Small(b)) => a == b,
+            (Self::Big(a), Self::Big(b)) => a == b,
+            (Self::Small(small), Self::Big(big)) | (Self::Big(big), Self::Small(small)) => {
+                big.to_i128() == Some(*small)
+            }
+        }
+    }
+}
+
+impl Eq for Integer {}
+
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if let (Self::Small(a), Self::Small(b)) = (self, other) {
+            return a.cmp(b);
+        }
+
+        let mut difference = self.clone();
+        difference += &-other.clone();
+        if difference.is_zero() {
+            Ordering::Equal
+        } else if difference.is_negative() {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+}
+
+impl FromStr for Integer {
+    type Err = ParseIntegerError;
+
+    /// Parses `[+-]digits`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseIntegerError);
+        }
+
+        let n = Self::from_decimal_digits(digits.as_bytes());
+        Ok(if negative { -n } else { n })
+    }
+}
+
+impl Neg for Integer {
+    type Output = Integer;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Self::Small(n) => match n.checked_neg() {
+                Some(n) => Self::Small(n),
+                None => Self::Big(-BigInt::from_i128(n)),
+            },
+            Self::Big(n) => Self::Big(-n),
+        }
+    }
+}
+
+impl AddAssign<&Integer> for Integer {
+    fn add_assign(&mut self, rhs: &Integer) {
+        match (&mut *self, rhs) {
+            (Self::Small(a), Self::Small(b)) => match a.checked_add(*b) {
+                Some(sum) => *a = sum,
+                None => *self = Self::Big(BigInt::from_i128(*a) + BigInt::from_i128(*b)),
+            },
+            (Self::Big(a), Self::Small(b)) => *a += &BigInt::from_i128(*b),
+            (Self::Big(a), Self::Big(b)) => *a += b,
+            (Self::Small(_), Self::Big(b)) => {
+                let mut sum = std::mem::take(self).into_big();
+                sum += b;
+                *self = Self::Big(sum);
+            }
+        }
+    }
+}
+
+impl Mul for &Integer {
+    type Output = Integer;
+
+    fn mul(self, rhs: &Integer) -> Self::Output {
+        if let (Integer::Small(a), Integer::Small(b)) = (self, rhs) {
+            if let Some(product) = a.checked_mul(*b) {
+                return Integer::Small(product);
+            }
+        }
+
+        Integer::from_big(&self.to_big() * &rhs.to_big())
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Small(n) => fmt::Display::fmt(n, f),
+            Self::Big(n) => fmt::Display::fmt(n, f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stays_small_until_overflow() {
+        let mut sum = Integer::from_decimal_digits(b"170141183460469231731687303715884105727");
+        assert_eq!(sum, Integer::Small(i128::MAX));
+
+        sum += &Integer::Small(1);
+        assert!(matches!(sum, Integer::Big(_)));
+        assert_eq!(sum.to_string(), "170141183460469231731687303715884105728");
+
+        sum += &-Integer::Small(2);
+        assert_eq!(sum.to_string(), "170141183460469231731687303715884105726");
+    }
+
+    #[test]
+    fn compares_by_value_across_representations() {
+        let big = Integer::Big(BigInt::from_i128(-7));
+        assert_eq!(big, Integer::Small(-7));
+        assert_ne!(big, Integer::Small(7));
+        assert!(big < Integer::Small(-6));
+        assert!(Integer::Small(i128::MAX) < Integer::Small(i128::MAX).mul_pow10(1));
+    }
+
+    #[test]
+    fn takes_square_roots_rounded_down() {
+        for (n, expected) in [
+            ("0", "0"),
+            ("1", "1"),
+            ("15", "3"),
+            ("16", "4"),
+            ("99", "9"),
+        ] {
+            let n = n.parse::<Integer>().unwrap();
+            assert_eq!(n.sqrt().to_string(), expected);
+        }
+
+        let big = Integer::Small(1).mul_pow10(400);
+        assert_eq!(big.sqrt(), Integer::Small(1).mul_pow10(200));
+        let mut below = big.clone();
+        below += &Integer::Small(-1);
+        assert_eq!(below.sqrt().to_string(), "9".repeat(200));
+    }
+
+    #[test]
+    fn can_multiply_divide_and_gcd_past_i128() {
+        let a = "-340282366920938463463374607431768211456"
+            .parse::<Integer>()
+            .unwrap();
+        let b = Integer::Small(1 << 100);
+
+        let product = &a * &b;
+        assert_eq!(product.div_rem(&a), (b.clone(), Integer::zero()));
+        assert_eq!(a.gcd(&Integer::Small(-48)), Integer::Small(16));
+        assert_eq!(product.gcd(&a), a.abs());
+    }
+
+    #[test]
+    fn rounds_halves_away_from_zero() {
+        for (n, d, expected) in [
+            (7, 2, 4),
+            (-7, 2, -4),
+            (7, -2, -4),
+            (5, 3, 2),
+            (4, 3, 1),
+            (6, 3, 2),
+        ] {
+            assert_eq!(
+                Integer::Small(n).div_round(&Integer::Small(d)),
+                Integer::Small(expected),
+                "{} / {}",
+                n,
+                d
+            );
+        }
+    }
+
+    #[test]
+    fn converts_to_and_from_other_radixes() {
+        assert_eq!(Integer::from_str_radix("-fF", 16), Ok(Integer::Small(-255)));
+        assert_eq!(Integer::from_str_radix("1012", 2), Err(ParseIntegerError));
+        assert_eq!(Integer::Small(-255).to_str_radix(16), "-ff");
+        assert_eq!(
+            Integer::Small(i128::MIN).to_str_radix(2),
+            format!("-1{}", "0".repeat(127))
+        );
+
+        let big = Integer::from_str_radix(&"f".repeat(40), 16).unwrap();
+        assert!(matches!(big, Integer::Big(_)));
+        assert_eq!((-big).to_str_radix(16), format!("-{}", "f".repeat(40)));
+    }
+
+    #[test]
+    fn promotes_when_scaling_overflows() {
+        let n = Integer::Small(-123).mul_pow10(40);
+        assert_eq!(n.to_string(), format!("-123{}", "0".repeat(40)));
+    }
+}
@@ -1,103 +1,1363 @@
+mod bigint;
+mod currency;
+mod decimal;
+mod duration;
+mod error;
+mod format;
+mod integer;
+mod number;
+mod parallel;
+mod precision;
+mod rates;
+mod rational;
+mod regex;
+mod size;
+mod summation;
+mod tokenizer;
+mod uncertain;
+mod unit;
+mod words;
+
 use std::{
-    env, fs,
-    io::{stdin, Read},
+    borrow::Cow,
+    env,
+    fs::File,
+    io::{self, stdin, Read},
+    mem,
+    num::NonZeroUsize,
     path::PathBuf,
     process,
+    str::FromStr,
+    thread,
 };
 
-const HELP_MENU: &'static str = r#"
-Sums up space and or newline delimited numbers (both integers and decimals) and prints result to stdout.
-Input can be from stdin (no flag) or a file (-f flag).
-Note: Commas in the numbers are allowed.
+use currency::{currency_of, split_currency};
+use decimal::Decimal;
+use duration::{Duration, DurationFormat};
+use error::{Error, ParseError, ParseErrors, Skipped};
+use format::{is_accounting_suffix, NumberFormat};
+use integer::Integer;
+use number::{is_mixed_fraction, parse_exact, Number};
+use precision::{decimal_places, scaled_places, Precision, Rounding, MAX_PLACES};
+use rates::{Conversion, Rates};
+use rational::Rational;
+use size::{format_size, is_size_suffix, split_size, SizeStyle};
+use summation::{Accumulator, Algorithm, ExactSum, FloatSum, Totals};
+use tokenizer::{Delimiter, Token, Tokenizer};
+use uncertain::{split_uncertainty, Uncertain, SEPARATORS};
+use unit::{split_unit, unit_of, Unit};
+use words::parse_words;
+
+const HELP_MENU: &str = r#"
+Sums up whitespace delimited numbers (both integers and decimals) and prints result to stdout.
+Input can be from stdin (no flag), numbers given as arguments, or a file (-f flag).
+Note: Digits can be grouped with commas, or as in a locale's number format (see --locale).
+Scientific notation such as 1e-3 or 6.02E23 is read in every mode, and in the integer mode if
+the number is an integer.
+Underscores can group the digits of integers, as in 1_000_000.
+Fractions (3/4, ¾), mixed numbers (1½) and percentages (15% for 0.15) are read exactly, and
+fail in the decimal and integer modes if they can't be held exactly, as 1/3 can't.
+Amounts in a currency, such as $1,200.50, €30, 12.00 USD or JPY 5000, are totaled separately
+for each currency, one line each.
+
+Options:
+    -f <path>            Read numbers from a file.
+    -m, --mode <mode>    Numeric type used for the sum:
+                           decimal   exact, keeps the largest number of decimal places (default)
+                           integer   exact, only accepts integers
+                           rational  exact, also accepts fractions such as 1/3
+                           duration  exact, sums durations such as 1:30:15 (or 1:30 for hours
+                                     and minutes), 1h30m15s, PT1H30M or a number of seconds
+                           uncertainty
+                                     exact, sums measurements such as 1.5±0.1 or 1.5+/-0.1 and
+                                     prints the total's uncertainty as `sum ± uncertainty`.
+                                     Both are decimals, with a unit after both with --units,
+                                     as in 1.5±0.1kg, so 1/3±0.1 and 200g±5g aren't read
+                           f64       64 bit floating point
+                           f32       32 bit floating point
+    --duration-format <format>
+                         How the duration mode prints the sum: clock (1:30:15, the default),
+                         go (1h30m15s), iso (PT1H30M15S) or seconds (5415).
+    --correlated         Add up uncertainties in the uncertainty mode, as they are for
+                         correlated errors, instead of taking the root sum of their squares.
+    -a, --algorithm <algorithm>
+                         Summation algorithm for the f32 and f64 modes:
+                           naive     add left to right (default)
+                           kahan     Kahan compensated summation
+                           neumaier  Neumaier compensated summation
+                           pairwise  pairwise summation
+    --non-finite <policy>
+                         What to do with inf, -Infinity and NaN in the f32 and f64 modes:
+                           reject     fail, as the other modes do (default)
+                           skip       leave them out, and print how many were to stderr
+                           propagate  add them, making the sum infinite or NaN
+    -e, --error-bound    Print an estimated bound on the floating point error as `sum ± bound`.
+    --output-radix <radix>
+                         Print the sum in the integer mode in hex, oct, bin or dec, or as a
+                         radix of 16, 8, 2 or 10. Input can be in any of them, as in 0xFF,
+                         0o755 and 0b1010.
+    --precision <places> Round the printed sum to this many decimal places, or with inputs to
+                         as many as the least precise number added up has, the usual rule for
+                         sums of measurements (1.25 + 3.1 is 4.4).
+    --rounding <mode>    How --precision rounds: half-even (the default), half-up, floor, ceil
+                         or truncate.
+    --sizes              Read sizes such as 1.5K, 200MiB, 3G, 12kB or 512 B as a number of
+                         bytes: K, M, G, ... and Ki, Mi, Gi, ... are powers of 1024 (as printed
+                         by du -h and ls -lh), kB, MB, GB, ... powers of 1000, so KB is 1000
+                         although K is 1024. They take precedence over the suffixes of --words,
+                         such as the M of 3M.
+    --human-readable     Print the sum as a size in powers of 1024, such as 1.5K or 200M, as
+                         du -h does. Implies --sizes.
+    --si                 Like --human-readable, in powers of 1000, such as 1.5kB or 200MB.
+    -r, --running        Print the running total after every line of input, for input that
+                         keeps arriving such as `tail -f`.
+    -j, --threads <n>    Number of threads used to sum a file (-f). Defaults to the number of
+                         cores. The result doesn't depend on the number of threads.
+    --max-errors <n>     Keep going after a number fails to parse and report up to n of them
+                         together, with their count. 0 reports all of them. Defaults to 1.
+    -s, --skip-invalid   Leave out words that aren't numbers instead of failing, and print how
+                         many were skipped to stderr.
+    --list-skipped       Like --skip-invalid, also listing each skipped word and its location.
+    --locale <locale>    Read numbers in the format of a locale such as de_DE (1.234,5), or of
+                         the one set by LC_ALL, LC_NUMERIC or LANG with env. The grouping of
+                         env is strict, and it rejects numbers such as 1.500 whose separator
+                         could be grouping or a decimal point.
+    --decimal <char>     Character used as the decimal point.
+    --group <chars>      Characters allowed between digits to group them, which are ignored.
+    --strict-grouping    Only allow grouping between groups of three digits before the decimal
+                         point, or as in the locale (1,00,000 for en_IN), so 1,00 is an error.
+    --accounting         Read negatives written in accounting notation: (1,234.56), 1234.56-,
+                         −12 with a Unicode minus, and 1,234.56 CR. DR marks positives.
+    --mixed-numbers      Read a whole number followed by a fraction, as in 1 1/2, as one number
+                         instead of two.
+    --percent-points     Read percentages as percentage points, so 15% is 15 instead of 0.15.
+    --words              Read numbers written in English words, such as twelve, one hundred and
+                         five or 3 million, and with a scale suffix, such as 2.5k or 3bn.
+    --units              Read amounts with a unit, such as 3.2kg, 12m or 5 km, convert them to a
+                         common unit and sum them if they are of the same dimension (mass,
+                         length, time or volume), failing otherwise. Units written apart are
+                         only read after a plain number, and words such as in and min only
+                         when no word follows, so 3 in stock is the number 3.
+    --unit <unit>        Print the sum of amounts with a unit in this unit, such as kg or ft,
+                         instead of the largest metric unit it converts to exactly. Implies
+                         --units.
+    --single-currency    Fail when amounts are in more than one currency instead of printing a
+                         total for each of them.
+    --to <currency>      Convert amounts in other currencies to this one with the rates of
+                         --rates, and print their total. The subtotal of each currency and the
+                         rate it was converted at are printed to stderr.
+    --rates <path>       Exchange rates for --to, as units of each currency per unit of a base
+                         currency: a CSV file with a currency,rate line per currency, or a JSON
+                         object such as {"base": "EUR", "rates": {"USD": 1.08}}.
+    -d, --delimiter <string>
+                         Split lines on a string such as , or \t instead of on spaces. Spaces
+                         around numbers are ignored, and \t, \0 and \\ stand for a tab, NUL
+                         and backslash.
+    --delimiter-regex <regex>
+                         Split lines on matches of a regular expression, such as \s*[,;]\s*.
+    -h, --help           Print this help menu.
+
+Exit codes:
+    64    Invalid arguments.
+    65    A number couldn't be parsed. Its location is printed to stderr.
+    74    The input couldn't be read.
 "#;
 
 #[derive(Debug, PartialEq, Eq)]
 enum Config {
+    Sum(Box<Options>),
+    PrintHelp,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+    input: Input,
+    mode: Mode,
+    duration_format: Option<DurationFormat>,
+    correlated: bool,
+    algorithm: Algorithm,
+    non_finite: NonFinite,
+    error_bound: bool,
+    output_radix: Option<u32>,
+    precision: Option<Precision>,
+    rounding: Option<Rounding>,
+    size_style: Option<SizeStyle>,
+    sizes: bool,
+    running: bool,
+    threads: Option<usize>,
+    max_errors: Option<usize>,
+    skip_invalid: bool,
+    list_skipped: bool,
+    locale: Option<NumberFormat>,
+    decimal: Option<char>,
+    grouping: Option<Vec<char>>,
+    strict_grouping: bool,
+    accounting: bool,
+    mixed_numbers: bool,
+    percent_points: bool,
+    words: bool,
+    units: bool,
+    unit: Option<&'static Unit>,
+    single_currency: bool,
+    to: Option<&'static str>,
+    rates: Option<PathBuf>,
+    delimiter: Delimiter,
+}
+
+impl Options {
+    fn parsing(&self) -> Result<Parsing, Error> {
+        let on_invalid = if self.skip_invalid || self.list_skipped {
+            OnInvalid::Skip {
+                list: self.list_skipped,
+            }
+        } else {
+            match self.max_errors {
+                Some(0) => OnInvalid::Fail(usize::MAX),
+                max_errors => OnInvalid::Fail(max_errors.unwrap_or(1)),
+            }
+        };
+
+        Ok(Parsing {
+            delimiter: self.delimiter.clone(),
+            format: self.number_format(),
+            on_invalid,
+            unit: self.unit,
+            non_finite: self.non_finite,
+            single_currency: self.single_currency,
+            conversion: self.conversion()?,
+        })
+    }
+
+    /// Conversion into the currency of `--to`, with the rates read from `--rates`.
+    fn conversion(&self) -> Result<Option<Conversion>, Error> {
+        let (Some(to), Some(path)) = (self.to, &self.rates) else {
+            return Ok(None);
+        };
+
+        let rates = Rates::from_file(path)?;
+        if !rates.covers(to) {
+            return Err(Error::Usage(format!(
+                "{}: there is no exchange rate for {}.",
+                path.display(),
+                to
+            )));
+        }
+        Ok(Some(Conversion { to, rates }))
+    }
+
+    /// Format numbers are read in: the one of `--locale` or the default, with the separators
+    /// given on the command line.
+    fn number_format(&self) -> NumberFormat {
+        let mut format = self.locale.clone().unwrap_or_default();
+        if let Some(decimal) = self.decimal {
+            format = format.with_decimal(decimal);
+        }
+        if let Some(grouping) = &self.grouping {
+            format.grouping = grouping.clone();
+        }
+        format.grouping.retain(|&c| c != format.decimal);
+        format.strict |= self.strict_grouping;
+        format.accounting = self.accounting;
+        format.mixed_numbers = self.mixed_numbers;
+        format.percent_points = self.percent_points;
+        format.words = self.words;
+        // Printing sizes is for summing them
+        format.sizes = self.sizes || self.size_style.is_some();
+        format.units = self.units || self.unit.is_some();
+        format.uncertainties = self.mode == Mode::Uncertainty;
+        format
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum Input {
+    #[default]
     Stdin,
     CliArg(String),
     File(PathBuf),
-    PrintHelp,
 }
 
-fn main() -> Result<(), String> {
-    let config = parse_args(env::args().collect())?;
+impl Input {
+    /// Name of the input in error messages.
+    fn name(&self) -> String {
+        match self {
+            Self::Stdin => "<stdin>".to_owned(),
+            Self::CliArg(_) => "<arguments>".to_owned(),
+            Self::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    F32,
+    F64,
+    #[default]
+    Decimal,
+    Integer,
+    Rational,
+    Duration,
+    Uncertainty,
+}
+
+impl Mode {
+    fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
 
-    let num_str = match config {
-        Config::Stdin => {
-            let mut buf = String::new();
-            stdin().read_to_string(&mut buf).unwrap();
-            buf
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f32" => Ok(Self::F32),
+            "f64" => Ok(Self::F64),
+            "decimal" => Ok(Self::Decimal),
+            "integer" => Ok(Self::Integer),
+            "rational" => Ok(Self::Rational),
+            "duration" => Ok(Self::Duration),
+            "uncertainty" => Ok(Self::Uncertainty),
+            _ => Err(format!(
+                "Unknown mode '{}'. Expected one of: decimal, integer, rational, duration, uncertainty, f64, f32.",
+                s
+            )),
         }
-        Config::CliArg(input) => input,
-        Config::File(path) => fs::read_to_string(path).map_err(|e| e.to_string())?,
+    }
+}
+
+fn main() {
+    if let Err(e) = run(env::args().collect()) {
+        eprintln!("Error: {}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), Error> {
+    let mut options = match parse_args(args).map_err(Error::Usage)? {
+        Config::Sum(options) => *options,
         Config::PrintHelp => {
             print_help();
             process::exit(0);
         }
     };
 
-    let sum = parse_num_str(num_str)?.iter().sum::<f32>();
-
-    println!("{}", sum);
-
-    Ok(())
+    let input = mem::take(&mut options.input);
+    let algorithm = options.algorithm;
+    match options.mode {
+        Mode::F32 => print_sum(input, FloatSum::<f32>::new(algorithm), &options),
+        Mode::F64 => print_sum(input, FloatSum::<f64>::new(algorithm), &options),
+        Mode::Decimal => print_sum(input, ExactSum::<Decimal>::default(), &options),
+        Mode::Integer => print_sum(input, ExactSum::<Integer>::default(), &options),
+        Mode::Rational => print_sum(input, ExactSum::<Rational>::default(), &options),
+        Mode::Duration => print_sum(input, ExactSum::<Duration>::default(), &options),
+        Mode::Uncertainty => print_sum(input, ExactSum::<Uncertain>::default(), &options),
+    }
 }
 
 fn parse_args(args: Vec<String>) -> Result<Config, String> {
+    let mut options = Options::default();
+    let mut nums = Vec::new();
+
     // Ignore very first argument given by OS
-    let mut args_iter = args.iter().skip(1);
+    let mut args_iter = args.into_iter().skip(1);
 
-    let args_len = args_iter.len();
+    while let Some(arg) = args_iter.next() {
+        // Long options also accept their value inline, as in `--mode=f64`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => {
+                (flag.to_owned(), Some(value.to_owned()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args_iter.next())
+                .ok_or_else(|| format!("Missing value for {}.", flag))
+        };
 
-    if let Some(first_arg) = args_iter.next() {
-        match first_arg.as_str() {
+        match flag.as_str() {
             "-f" => {
-                let path = args_iter
-                    .next()
-                    .ok_or_else(|| "Missing path to file.".to_owned())?;
-
-                Ok(Config::File(PathBuf::from(path)))
-            }
-            "-h" => Ok(Config::PrintHelp),
-            _ => {
-                let str_len = args.iter().map(|num_str| num_str.len()).sum::<usize>() + args_len;
-                let num_str = args.into_iter().skip(1).enumerate().fold(
-                    String::with_capacity(str_len),
-                    |mut acc, (i, num_str)| {
-                        acc.push_str(num_str.as_str());
-                        if i < args_len - 1 {
-                            acc.push(' ');
-                        }
-                        acc
-                    },
+                let path = value().map_err(|_| "Missing path to file.".to_owned())?;
+                options.input = Input::File(PathBuf::from(path));
+            }
+            "-m" | "--mode" => options.mode = value()?.parse()?,
+            "--duration-format" => options.duration_format = Some(value()?.parse()?),
+            "--correlated" => options.correlated = true,
+            "-a" | "--algorithm" => options.algorithm = value()?.parse()?,
+            "--non-finite" => options.non_finite = value()?.parse()?,
+            "-e" | "--error-bound" => options.error_bound = true,
+            "--output-radix" => {
+                let radix = value()?;
+                options.output_radix = Some(match radix.as_str() {
+                    "hex" | "16" => 16,
+                    "oct" | "8" => 8,
+                    "bin" | "2" => 2,
+                    "dec" | "10" => 10,
+                    _ => {
+                        return Err(format!(
+                            "Unknown radix '{}'. Expected one of: hex, oct, bin, dec.",
+                            radix
+                        ))
+                    }
+                });
+            }
+            "--precision" => options.precision = Some(value()?.parse()?),
+            "--rounding" => options.rounding = Some(value()?.parse()?),
+            "--human-readable" => options.size_style = Some(SizeStyle::Iec),
+            "--si" => options.size_style = Some(SizeStyle::Si),
+            "--sizes" => options.sizes = true,
+            "-r" | "--running" => options.running = true,
+            "-j" | "--threads" => {
+                let threads = value()?;
+                match threads.parse() {
+                    Ok(threads) if threads > 0 => options.threads = Some(threads),
+                    _ => return Err(format!("Invalid number of threads '{}'.", threads)),
+                }
+            }
+            "--max-errors" => {
+                let max_errors = value()?;
+                match max_errors.parse() {
+                    Ok(max_errors) => options.max_errors = Some(max_errors),
+                    _ => return Err(format!("Invalid number of errors '{}'.", max_errors)),
+                }
+            }
+            "-s" | "--skip-invalid" => options.skip_invalid = true,
+            "--list-skipped" => options.list_skipped = true,
+            "--locale" => {
+                let locale = value()?;
+                let format = match locale.as_str() {
+                    "env" => NumberFormat::from_env()?,
+                    _ => NumberFormat::from_locale(&locale)
+                        .ok_or_else(|| format!("Unknown locale '{}'.", locale))?,
+                };
+                options.locale = Some(format);
+            }
+            "--decimal" => match separators(&value()?)?[..] {
+                [decimal] => options.decimal = Some(decimal),
+                _ => return Err("The decimal point must be a single character.".to_owned()),
+            },
+            "--group" => options.grouping = Some(separators(&value()?)?),
+            "--strict-grouping" => options.strict_grouping = true,
+            "--accounting" => options.accounting = true,
+            "--mixed-numbers" => options.mixed_numbers = true,
+            "--percent-points" => options.percent_points = true,
+            "--words" => options.words = true,
+            "--units" => options.units = true,
+            "--unit" => {
+                let unit = value()?;
+                options.unit =
+                    Some(unit_of(&unit).ok_or_else(|| format!("Unknown unit '{}'.", unit))?);
+            }
+            "--single-currency" => options.single_currency = true,
+            "--to" => {
+                let to = value()?;
+                let currency =
+                    currency_of(&to).ok_or_else(|| format!("Unknown currency '{}'.", to))?;
+                options.to = Some(currency);
+            }
+            "--rates" => options.rates = Some(PathBuf::from(value()?)),
+            "-d" | "--delimiter" => {
+                options.delimiter = Delimiter::Literal(unescape_delimiter(&value()?)?);
+            }
+            "--delimiter-regex" => {
+                let regex = value()?;
+                if regex.is_empty() {
+                    return Err("The delimiter regex can't be empty.".to_owned());
+                }
+                options.delimiter = Delimiter::Regex(regex.parse()?);
+            }
+            "-h" | "--help" => return Ok(Config::PrintHelp),
+            "--" => nums.extend(args_iter.by_ref()),
+            _ if flag.starts_with("--") => return Err(format!("Unknown option '{}'.", flag)),
+            _ => nums.push(flag),
+        }
+    }
+
+    if !options.mode.is_float() && (options.algorithm != Algorithm::Naive || options.error_bound) {
+        return Err(
+            "--algorithm and --error-bound only apply to the f32 and f64 modes.".to_owned(),
+        );
+    }
+
+    if !options.mode.is_float() && options.non_finite != NonFinite::Reject {
+        return Err("--non-finite only applies to the f32 and f64 modes.".to_owned());
+    }
+
+    if options.mode != Mode::Integer && options.output_radix.is_some() {
+        return Err("--output-radix only applies to the integer mode.".to_owned());
+    }
+
+    if options.mode != Mode::Duration && options.duration_format.is_some() {
+        return Err("--duration-format only applies to the duration mode.".to_owned());
+    }
+
+    if options.mode != Mode::Uncertainty && options.correlated {
+        return Err("--correlated only applies to the uncertainty mode.".to_owned());
+    }
+
+    // Durations have units of their own, read as such
+    if options.mode == Mode::Duration && (options.units || options.unit.is_some()) {
+        return Err("--units and --unit don't apply to the duration mode.".to_owned());
+    }
+
+    if options.output_radix.is_some() && options.size_style.is_some() {
+        return Err("--output-radix can't be combined with --human-readable or --si.".to_owned());
+    }
+
+    if options.rounding.is_some() && options.precision.is_none() {
+        return Err("--rounding only applies with --precision.".to_owned());
+    }
+
+    if options.precision.is_some() {
+        if options.mode == Mode::Duration {
+            return Err("--precision doesn't apply to the duration mode.".to_owned());
+        }
+        if options.output_radix.is_some() || options.size_style.is_some() {
+            return Err(
+                "--precision can't be combined with --output-radix, --human-readable or --si."
+                    .to_owned(),
+            );
+        }
+    }
+
+    if (options.skip_invalid || options.list_skipped) && options.max_errors.is_some() {
+        return Err("--max-errors can't be combined with skipping invalid numbers.".to_owned());
+    }
+
+    if options.to.is_some() != options.rates.is_some() {
+        return Err("--to and --rates have to be given together.".to_owned());
+    }
+
+    if let (Some(decimal), Some(grouping)) = (options.decimal, &options.grouping) {
+        if grouping.contains(&decimal) {
+            return Err(format!(
+                "'{}' can't be both the decimal point and grouping.",
+                decimal
+            ));
+        }
+    }
+
+    if !nums.is_empty() {
+        if options.input != Input::Stdin {
+            return Err("Numbers can't be given as arguments when reading from a file.".to_owned());
+        }
+        options.input = Input::CliArg(nums.join(" "));
+    }
+
+    Ok(Config::Sum(Box::new(options)))
+}
+
+/// Parses the value of `--decimal` or `--group`, which can't contain characters that are part of
+/// numbers or separate them.
+fn separators(value: &str) -> Result<Vec<char>, String> {
+    match value
+        .chars()
+        .find(|&c| c.is_ascii_alphanumeric() || "+-/ \n".contains(c))
+    {
+        Some(c) => Err(format!("'{}' can't be used as a separator in numbers.", c)),
+        None => Ok(value.chars().collect()),
+    }
+}
+
+/// Replaces the escapes allowed in `--delimiter` by the characters they stand for.
+fn unescape_delimiter(value: &str) -> Result<String, String> {
+    let mut delimiter = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        delimiter.push(match c {
+            '\\' => match chars.next() {
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                _ => return Err(format!("Invalid escape in delimiter '{}'.", value)),
+            },
+            '\n' => return Err("The delimiter can't contain a newline.".to_owned()),
+            c => c,
+        });
+    }
+
+    if delimiter.is_empty() {
+        return Err("The delimiter can't be empty.".to_owned());
+    }
+    Ok(delimiter)
+}
+
+fn print_sum<A: Accumulator + Clone + Send + Sync>(
+    input: Input,
+    sum: A,
+    options: &Options,
+) -> Result<(), Error> {
+    let parsing = options.parsing()?;
+    let conversion = parsing.conversion.as_ref();
+    let printing = Printing {
+        error_bound: options.error_bound,
+        radix: options.output_radix,
+        precision: options.precision,
+        rounding: options.rounding.unwrap_or_default(),
+        size_style: options.size_style,
+        duration_format: options.duration_format,
+        correlated: options.correlated,
+        unit: options.unit,
+        conversion,
+    };
+
+    let mut printed = false;
+    let on_line_end = |sum: &Totals<A>| {
+        if options.running {
+            println!("{}", format_totals(sum, &printing));
+            printed = true;
+        }
+    };
+
+    let source = input.name();
+    let (sum, skipped) = match input {
+        // A running total has to be produced in input order, and so do errors about currencies
+        // that don't match earlier ones, so they can't be split up
+        Input::File(path) if !options.running && !options.single_currency => {
+            let threads = options
+                .threads
+                .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+            parallel::sum_file(&path, threads, |chunk| {
+                sum_num_str(chunk, &source, sum.clone(), &parsing, |_| {})
+            })?
+        }
+        Input::File(path) => {
+            let file = File::open(path).map_err(|e| Error::Io(format!("{}: {}", source, e)))?;
+            sum_num_str(file, &source, sum, &parsing, on_line_end)?
+        }
+        Input::Stdin => sum_num_str(stdin().lock(), &source, sum, &parsing, on_line_end)?,
+        Input::CliArg(input) => sum_num_str(input.as_bytes(), &source, sum, &parsing, on_line_end)?,
+    };
+
+    check_dimensions(&sum, &source)?;
+    if !printed {
+        println!("{}", format_totals(&sum, &printing));
+    }
+
+    if let Some(conversion) = conversion {
+        let converted = convert(&sum, conversion);
+        if !converted.is_empty() {
+            eprintln!("Converted to {}:", conversion.to);
+        }
+        for Converted {
+            currency,
+            subtotal,
+            rate,
+            amount,
+            ..
+        } in converted
+        {
+            if currency == conversion.to {
+                eprintln!("    {} {}", amount, currency);
+            } else {
+                eprintln!(
+                    "    {} {} at {} = {} {}",
+                    subtotal.total(),
+                    currency,
+                    rate.to_f64(),
+                    amount,
+                    conversion.to
                 );
+            }
+        }
+    }
+
+    if skipped.non_finite > 0 {
+        eprintln!(
+            "Skipped {} infinit{} or NaN{}.",
+            skipped.non_finite,
+            if skipped.non_finite == 1 { "y" } else { "ies" },
+            if skipped.non_finite == 1 { "" } else { "s" }
+        );
+    }
+
+    if skipped.count > 0 {
+        eprintln!(
+            "Skipped {} token{} that couldn't be parsed{}",
+            skipped.count,
+            if skipped.count == 1 { "" } else { "s" },
+            if skipped.tokens.is_empty() { "." } else { ":" }
+        );
+        for token in &skipped.tokens {
+            eprintln!(
+                "    {}:{}:{}  {}",
+                token.source, token.line, token.column, token.token
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// How the input is split into tokens, and how they are parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Parsing {
+    delimiter: Delimiter,
+    format: NumberFormat,
+    on_invalid: OnInvalid,
+    /// Unit of `--unit`, which amounts with a unit of another dimension fail to parse in.
+    unit: Option<&'static Unit>,
+    non_finite: NonFinite,
+    /// Whether an amount in a different currency than earlier ones fails to parse.
+    single_currency: bool,
+    /// Conversion the amounts are totaled in, which amounts in currencies it has no rate for
+    /// fail to parse in.
+    conversion: Option<Conversion>,
+}
+
+impl Default for Parsing {
+    fn default() -> Self {
+        Self {
+            delimiter: Delimiter::Whitespace,
+            format: NumberFormat::default(),
+            on_invalid: OnInvalid::Fail(1),
+            unit: None,
+            non_finite: NonFinite::Reject,
+            single_currency: false,
+            conversion: None,
+        }
+    }
+}
+
+/// What to do with tokens that fail to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnInvalid {
+    /// Collect up to the given number of them and fail once the input ends or the limit is hit.
+    Fail(usize),
+    /// Leave them out of the sum, keeping each of them if they are listed.
+    Skip { list: bool },
+}
+
+/// What to do with infinities and NaN, which only the floating point modes can parse.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum NonFinite {
+    /// Fail to parse them, so a single NaN can't silently make the sum NaN.
+    #[default]
+    Reject,
+    /// Leave them out of the sum, counting them.
+    Skip,
+    /// Add them to the sum.
+    Propagate,
+}
+
+impl FromStr for NonFinite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(Self::Reject),
+            "skip" => Ok(Self::Skip),
+            "propagate" => Ok(Self::Propagate),
+            _ => Err(format!(
+                "Unknown policy '{}'. Expected one of: reject, skip, propagate.",
+                s
+            )),
+        }
+    }
+}
+
+/// Sums the numbers in `input` as they are read, calling `on_line_end` with the running totals
+/// each time a line containing numbers has been summed.
+///
+/// Amounts in each currency are summed separately, each starting from `sum`. `source` names the
+/// input in error messages. Tokens that fail to parse are handled according to `parsing`, and
+/// those that are skipped are returned alongside the totals.
+fn sum_num_str<A: Accumulator + Clone>(
+    input: impl Read,
+    source: &str,
+    sum: A,
+    parsing: &Parsing,
+    mut on_line_end: impl FnMut(&Totals<A>),
+) -> Result<(Totals<A>, Skipped), Error> {
+    let Parsing {
+        delimiter,
+        format,
+        on_invalid,
+        unit,
+        non_finite,
+        single_currency,
+        conversion,
+    } = parsing;
+    let on_invalid = *on_invalid;
+    let mut tokens = Tokenizer::new(input, delimiter.clone()).with_word_chars(&format.grouping);
+    let mut totals = Totals::new(sum);
+    let mut first_currency = None;
+    let mut pending_line = None;
+    let mut errors = Vec::new();
+    let mut skipped = Skipped::default();
+
+    while let Some(token) =
+        next_term(&mut tokens, format).map_err(|e| Error::Io(format!("{}: {}", source, e)))?
+    {
+        if pending_line.is_some_and(|line| line < token.line) {
+            on_line_end(&totals);
+        }
+
+        let parsed =
+            parse_num_str::<A::Value>(&token.text, format).and_then(|(n, currency, places)| {
+                match non_finite {
+                    _ if n.is_finite() => {}
+                    NonFinite::Reject => {
+                        return Err(format!(
+                            "'{}' isn't a finite number, which --non-finite can skip or add",
+                            token.text
+                        ))
+                    }
+                    NonFinite::Skip => return Ok(None),
+                    NonFinite::Propagate => {}
+                }
+
+                if let Some(quantity_unit) = currency.and_then(unit_of) {
+                    check_dimension(&token.text, quantity_unit, *unit, &totals)?;
+                    return Ok(Some((n, currency, places)));
+                }
+
+                match (currency, first_currency) {
+                    (Some(currency), Some(first)) if *single_currency && currency != first => {
+                        Err(format!(
+                            "'{}' is in {}, but earlier amounts are in {}",
+                            token.text, currency, first
+                        ))
+                    }
+                    (Some(currency), _)
+                        if conversion
+                            .as_ref()
+                            .is_some_and(|conversion| conversion.rate(currency).is_none()) =>
+                    {
+                        Err(format!(
+                            "There is no exchange rate to convert '{}' from {}",
+                            token.text, currency
+                        ))
+                    }
+                    _ => {
+                        first_currency = first_currency.or(currency);
+                        Ok(Some((n, currency, places)))
+                    }
+                }
+            });
+        match (parsed, on_invalid) {
+            (Ok(Some((n, currency, places))), _) => {
+                totals.add(currency, &n);
+                if let Some(places) = places {
+                    totals.add_places(currency, places);
+                }
+            }
+            (Ok(None), _) => skipped.non_finite += 1,
+            (Err(_), OnInvalid::Skip { list: false }) => skipped.count += 1,
+            (Err(message), _) => {
+                let error = ParseError {
+                    message,
+                    source: source.to_owned(),
+                    line: token.line,
+                    column: token.column,
+                    // Skipped tokens are listed by their location only
+                    line_text: match on_invalid {
+                        OnInvalid::Fail(_) => tokens.line_context().ok().flatten(),
+                        OnInvalid::Skip { .. } => None,
+                    },
+                    token: token.text,
+                };
+
+                if let OnInvalid::Fail(max_errors) = on_invalid {
+                    errors.push(error);
+                    if errors.len() >= max_errors {
+                        break;
+                    }
+                } else {
+                    skipped.count += 1;
+                    skipped.tokens.push(error);
+                }
+            }
+        }
+
+        if tokens.at_line_end() {
+            on_line_end(&totals);
+            pending_line = None;
+        } else {
+            pending_line = Some(token.line);
+        }
+    }
+
+    if let OnInvalid::Fail(max_errors) = on_invalid {
+        if !errors.is_empty() {
+            return Err(Error::Parse(ParseErrors {
+                errors,
+                limit: max_errors,
+            }));
+        }
+    }
+
+    if pending_line.is_some() {
+        on_line_end(&totals);
+    }
+
+    Ok((totals, skipped))
+}
+
+/// Fails if an amount in `unit` can't be added to the amounts with a unit summed so far, or be
+/// printed in `--unit`.
+fn check_dimension<A: Accumulator + Clone>(
+    text: &str,
+    unit: &Unit,
+    chosen: Option<&Unit>,
+    totals: &Totals<A>,
+) -> Result<(), String> {
+    if let Some(chosen) = chosen.filter(|chosen| chosen.dimension != unit.dimension) {
+        return Err(format!(
+            "'{}' is a {}, which can't be converted to {}",
+            text, unit.dimension, chosen.symbol
+        ));
+    }
+
+    let earlier = totals.iter().find_map(|(key, _)| key.and_then(unit_of));
+    match earlier {
+        Some(earlier) if earlier.dimension != unit.dimension => Err(format!(
+            "'{}' is a {}, which can't be added to the {} of earlier amounts",
+            text, unit.dimension, earlier.dimension
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Fails if amounts of different dimensions were summed, which can only happen in different
+/// chunks of a file summed in parallel since each is checked as it is parsed.
+fn check_dimensions<A: Accumulator + Clone>(totals: &Totals<A>, source: &str) -> Result<(), Error> {
+    let mut units = totals.iter().filter_map(|(key, _)| key.and_then(unit_of));
+    if let (Some(unit), Some(other)) = (units.next(), units.next()) {
+        return Err(Error::Data(format!(
+            "{}: a {} can't be added to a {}.",
+            source, unit.dimension, other.dimension
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the next token along with the ones following it on its line that are part of the same
+/// amount, such as the currency of `USD 12`, the words of `one hundred and five`, the fraction of
+/// `1 1/2`, the uncertainty of `1.5 ± 0.1`, the unit of `450 kg`, the suffix of `512 B` or the
+/// `CR` of `12.50 CR`.
+fn next_term(
+    tokens: &mut Tokenizer<impl Read>,
+    format: &NumberFormat,
+) -> io::Result<Option<Token>> {
+    let Some(mut term) = tokens.next_token()? else {
+        return Ok(None);
+    };
+
+    let starts_amount = |token: &Token| {
+        token
+            .text
+            .starts_with(|c: char| c.is_ascii_digit() || "+-\u{2212}(.".contains(c))
+    };
+    // Only numbers without anything around them take a size or unit written apart, as do
+    // measurements such as `0.5 ± 0.1` whose sides are
+    let is_bare_number = |token: &Token| {
+        let is_bare = |text: &str| {
+            let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+            digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+                && digits
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || ".,'_/".contains(c))
+        };
+        match split_uncertainty(&token.text).filter(|_| format.uncertainties) {
+            Some((value, uncertainty)) => is_bare(value.trim()) && is_bare(uncertainty.trim()),
+            None => is_bare(&token.text),
+        }
+    };
+    // A currency following an amount that has one already belongs to the next amount, and one
+    // following a word to neither
+    let joins_next = match tokens.peek_on_line(0)? {
+        Some(next) if currency_of(&term.text).is_some() => starts_amount(next),
+        Some(next) => {
+            currency_of(&next.text).is_some()
+                && starts_amount(&term)
+                && split_currency(&term.text).is_ok_and(|(currency, _)| currency.is_none())
+        }
+        None => false,
+    };
+    if joins_next {
+        join_next(&mut term, tokens)?;
+    }
+
+    // Number words run on for as long as they make up one number, so `one hundred and five` is
+    // one and `one two` is two
+    if format.words {
+        loop {
+            let mut phrase = term.text.clone();
+            let mut ahead = 0;
+            while let Some(next) = tokens.peek_on_line(ahead)? {
+                phrase = format!("{} {}", phrase, next.text);
+                ahead += 1;
+                // An `and` only joins along with the word after it
+                if ahead == 2 || !next.text.eq_ignore_ascii_case("and") {
+                    break;
+                }
+            }
+            let is_number = split_currency(&phrase)
+                .is_ok_and(|(_, amount)| read_words(&amount, format).is_some());
+            if ahead == 0 || !is_number {
+                break;
+            }
+            for _ in 0..ahead {
+                join_next(&mut term, tokens)?;
+            }
+        }
+    }
+
+    let whole = term.text.strip_prefix(['-', '+']).unwrap_or(&term.text);
+    if format.mixed_numbers
+        && !whole.is_empty()
+        && whole.bytes().all(|b| b.is_ascii_digit())
+        && tokens
+            .peek_on_line(0)?
+            .is_some_and(|next| is_mixed_fraction(&next.text))
+    {
+        join_next(&mut term, tokens)?;
+    }
+
+    // The uncertainty of `1.5 ± 0.1` belongs to the value before it
+    if format.uncertainties {
+        while let Some(next) = tokens.peek_on_line(0)? {
+            let at_separator = SEPARATORS.iter().any(|separator| {
+                term.text.ends_with(separator) || next.text.starts_with(separator)
+            });
+            if !at_separator {
+                break;
+            }
+            join_next(&mut term, tokens)?;
+        }
+    }
+
+    // Units that are words as well are only joined at the end of a phrase, so `3 in` is 3
+    // inches but `3 in stock` the number 3
+    if format.units && is_bare_number(&term) {
+        if let Some(unit) = tokens.peek_on_line(0)?.and_then(|next| unit_of(&next.text)) {
+            let word_follows = unit.is_word()
+                && tokens
+                    .peek_on_line(1)?
+                    .is_some_and(|next| next.text.starts_with(char::is_alphabetic));
+            if !word_follows {
+                join_next(&mut term, tokens)?;
+            }
+        }
+    }
+
+    if format.sizes
+        && is_bare_number(&term)
+        && tokens
+            .peek_on_line(0)?
+            .is_some_and(|next| is_size_suffix(&next.text))
+    {
+        join_next(&mut term, tokens)?;
+    }
+
+    if format.accounting
+        && tokens
+            .peek_on_line(0)?
+            .is_some_and(|next| is_accounting_suffix(&next.text))
+    {
+        join_next(&mut term, tokens)?;
+    }
+
+    Ok(Some(term))
+}
+
+/// Appends the next token to `term`, separated by a space.
+fn join_next(term: &mut Token, tokens: &mut Tokenizer<impl Read>) -> io::Result<()> {
+    let next = tokens.next_token()?.expect("peeked token");
+    term.text = format!("{} {}", term.text, next.text);
+    Ok(())
+}
+
+/// How sums are printed.
+#[derive(Default)]
+struct Printing<'a> {
+    error_bound: bool,
+    /// Radix of `--output-radix`, for the integer mode.
+    radix: Option<u32>,
+    /// Decimal places of `--precision`, rounded to with `rounding`.
+    precision: Option<Precision>,
+    rounding: Rounding,
+    /// Style of `--human-readable` or `--si`.
+    size_style: Option<SizeStyle>,
+    /// Format of `--duration-format`, for the duration mode.
+    duration_format: Option<DurationFormat>,
+    /// Whether uncertainties are added up linearly, for `--correlated`.
+    correlated: bool,
+    /// Unit of `--unit`, for amounts with a unit.
+    unit: Option<&'static Unit>,
+    conversion: Option<&'a Conversion>,
+}
+
+impl Printing<'_> {
+    /// Decimal places and rounding of a total whose numbers were written with `least_places`
+    /// decimal places at the fewest, or `None` if it is printed as is. Totals are rounded to
+    /// whole numbers at most, even if their numbers were written to tens or thousands.
+    fn rounded(&self, least_places: Option<i64>) -> Option<(u32, Rounding)> {
+        let places = match self.precision? {
+            Precision::Places(places) => places,
+            Precision::Inputs => least_places?.clamp(0, MAX_PLACES.into()) as u32,
+        };
+        Some((places, self.rounding))
+    }
+}
+
+/// Formats the total of each currency or unit on its own line, as `1200.5 USD` or `3.65 kg`, or
+/// with a conversion those of numbers without a currency and that of all amounts once converted.
+fn format_totals<A: Accumulator + Clone>(totals: &Totals<A>, printing: &Printing) -> String {
+    let format_total = |sum: &A, currency: Option<&'static str>| {
+        let least_places = totals.least_places(currency);
+        match currency {
+            Some(currency) => match unit_of(currency) {
+                Some(unit) => format_quantity(sum, unit, least_places, printing),
+                None => {
+                    let rounded = printing.rounded(least_places);
+                    format!("{} {}", format_sum(sum, rounded, printing), currency)
+                }
+            },
+            None => match printing.unit {
+                // Nothing summed is none of `--unit` either
+                Some(unit) if totals.is_empty() => {
+                    format_quantity(sum, unit.base(), None, printing)
+                }
+                _ => format_sum(sum, printing.rounded(least_places), printing),
+            },
+        }
+    };
 
-                Ok(Config::CliArg(num_str))
+    let Some(conversion) = printing.conversion else {
+        let lines = totals
+            .iter()
+            .map(|(currency, sum)| format_total(sum, currency));
+        return lines.collect::<Vec<_>>().join("\n");
+    };
+
+    let mut lines = totals
+        .iter()
+        .filter(|(currency, _)| currency.is_none_or(|currency| unit_of(currency).is_some()))
+        .map(|(currency, sum)| format_total(sum, currency))
+        .collect::<Vec<_>>();
+
+    let converted = convert(totals, conversion);
+    if !converted.is_empty() {
+        let mut total = A::Value::zero();
+        let mut bound = None;
+        for converted in &converted {
+            total += &converted.amount;
+            if let Some(converted_bound) = &converted.bound {
+                *bound.get_or_insert_with(A::Value::zero) += converted_bound;
             }
         }
-    } else {
-        Ok(Config::Stdin)
+
+        // Rounded as precisely as the least precise amount converted
+        let least_places = converted
+            .iter()
+            .filter_map(|converted| totals.least_places(Some(converted.currency)))
+            .min();
+        let amount = format_number(&total, printing.rounded(least_places), printing);
+        lines.push(match bound {
+            Some(bound) if printing.error_bound => {
+                format!("{} ± {} {}", amount, bound, conversion.to)
+            }
+            _ => format!("{} {}", amount, conversion.to),
+        });
     }
+    lines.join("\n")
+}
+
+/// Subtotal of a currency converted to the currency of a [`Conversion`].
+struct Converted<'a, A: Accumulator> {
+    currency: &'static str,
+    subtotal: &'a A,
+    rate: Rational,
+    amount: A::Value,
+    bound: Option<A::Value>,
 }
 
-fn parse_num_str(num_str: String) -> Result<Vec<f32>, String> {
-    let num_str = num_str.chars().filter(|&c| c != ',').collect::<String>();
-    let num_str = num_str
-        .trim()
-        .split('\n')
-        .map(|line| line.split(' '))
-        .flatten()
-        .collect::<Vec<&str>>();
+/// Converts the subtotal of each currency, rounding to the minor unit of the currency converted
+/// to. Amounts already in it are kept as they are.
+fn convert<'a, A: Accumulator + Clone>(
+    totals: &'a Totals<A>,
+    conversion: &Conversion,
+) -> Vec<Converted<'a, A>> {
+    let scale = conversion.scale();
+    let converted = totals.iter().filter_map(|(currency, subtotal)| {
+        let currency = currency.filter(|currency| unit_of(currency).is_none())?;
+        let rate = conversion
+            .rate(currency)
+            .expect("amounts without a rate fail to parse");
+
+        let (amount, bound) = if currency == conversion.to {
+            (subtotal.total(), subtotal.error_bound())
+        } else {
+            let bound = subtotal.error_bound().map(|b| b.scaled_by(&rate, scale));
+            (subtotal.total().scaled_by(&rate, scale), bound)
+        };
+        Some(Converted {
+            currency,
+            subtotal,
+            rate,
+            amount,
+            bound,
+        })
+    });
+    converted.collect()
+}
 
-    let parse_results: Vec<Result<f32, _>> = num_str.iter().map(|n| n.parse::<f32>()).collect();
+/// Formats the total of amounts summed in the base unit `base`, in the unit of `--unit` or else
+/// the one picked for it, as `3.65 kg`. It is rounded to places of the unit it is printed in,
+/// from `least_places` of the base unit.
+fn format_quantity<A: Accumulator>(
+    sum: &A,
+    base: &'static Unit,
+    least_places: Option<i64>,
+    printing: &Printing,
+) -> String {
+    let total = sum.total();
+    let unit = printing.unit.unwrap_or_else(|| base.pick_for(&total));
+    let least_places = least_places.map(|places| unit.places_of(places));
+    let amount = format_number(
+        &unit.amount_of(&total),
+        printing.rounded(least_places),
+        printing,
+    );
+    match sum.error_bound() {
+        Some(bound) if printing.error_bound => {
+            format!("{} ± {} {}", amount, unit.amount_of(&bound), unit.symbol)
+        }
+        _ => format!("{} {}", amount, unit.symbol),
+    }
+}
 
-    for (result, num_str) in parse_results.iter().zip(num_str.iter()) {
-        if let Err(_) = result {
-            return Err(format!("Failed to parse '{}'", num_str));
+/// Formats a total rounded as `rounded` says, with its estimated error bound if asked for. The
+/// bound isn't rounded, as it could round down to nothing.
+fn format_sum<A: Accumulator>(
+    sum: &A,
+    rounded: Option<(u32, Rounding)>,
+    printing: &Printing,
+) -> String {
+    let total = format_number(&sum.total(), rounded, printing);
+    match sum.error_bound() {
+        Some(bound) if printing.error_bound => {
+            format!("{} ± {}", total, format_number(&bound, None, printing))
         }
+        _ => total,
+    }
+}
+
+/// Formats a number in the radix, duration format or size style chosen, or rounded as `rounded`
+/// says.
+fn format_number<N: Number>(
+    n: &N,
+    rounded: Option<(u32, Rounding)>,
+    printing: &Printing,
+) -> String {
+    let formatted = match (
+        printing.radix,
+        printing.duration_format,
+        printing.size_style,
+    ) {
+        (Some(radix), _, _) => n.format_radix(radix),
+        (None, Some(format), _) => n.format_duration(format),
+        (None, None, Some(style)) => format_size(n.to_f64(), style),
+        (None, None, None) => n
+            .format_uncertain(printing.correlated, rounded)
+            .or_else(|| rounded.and_then(|(places, rounding)| n.format_rounded(places, rounding))),
+    };
+    formatted.unwrap_or_else(|| n.to_string())
+}
+
+/// Parses a number, along with what it is in if anything: the code of its currency, or the base
+/// unit of its unit, which it is converted to. Also returns the number of decimal places it is
+/// written with if it is a decimal, counted in bytes or the base unit for sizes and units.
+fn parse_num_str<N: Number>(
+    num_str: &str,
+    format: &NumberFormat,
+) -> Result<(N, Option<&'static str>, Option<i64>), String> {
+    let fail = |reason: String| format!("Failed to parse '{}': {}", num_str, reason);
+    let (currency, amount) = split_currency(num_str).map_err(fail)?;
+    let inexact =
+        || fail("it can't be held exactly in this mode, but can in --mode rational".to_owned());
+    let size = split_size(&amount).filter(|_| format.sizes);
+    let words = (format.words && size.is_none())
+        .then(|| read_words(&amount, format))
+        .flatten();
+    if let Some(words) = words {
+        return Ok((
+            N::from_rational(&words).ok_or_else(inexact)?,
+            currency,
+            None,
+        ));
+    }
+    let unit = split_unit(&amount).filter(|_| format.units);
+    let (amount, factor, unit) = match (unit, size) {
+        (Some((number, unit)), _) => (number, Some(unit.base_units()), Some(unit)),
+        (None, Some((number, bytes))) => (number, Some(Rational::from(bytes)), None),
+        (None, None) => (amount.as_ref(), None, None),
+    };
+    if let (Some(currency), Some(unit)) = (currency, unit) {
+        return Err(fail(format!(
+            "both {} and {} are given as its unit",
+            currency, unit.symbol
+        )));
     }
+    let mut normalized = format.normalize(amount).map_err(fail)?;
+    // Written as `1.5±0.1`, so the `/` of `+/-` and the space of `1.5 ± 0.1` aren't taken for a
+    // fraction or mixed number
+    if format.uncertainties && split_uncertainty(&normalized).is_some() {
+        normalized = Cow::Owned(normalized.replace("+/-", "±").replace(' ', ""));
+    }
+
+    let key = unit.map(|unit| unit.base().symbol).or(currency);
+    let invalid = || format!("Failed to parse '{}'", num_str);
+    // Precision is that of the value of a measurement such as `1.5±0.1`
+    let places = match split_uncertainty(&normalized) {
+        Some((value, _)) => decimal_places(value),
+        None => decimal_places(&normalized),
+    }
+    .map(|places| match &factor {
+        Some(factor) => scaled_places(places.into(), factor),
+        None => places.into(),
+    });
+
+    let exact = match factor {
+        // Measurements such as `1.5±0.1 kg` are scaled along with their uncertainty
+        Some(factor) if format.uncertainties && split_uncertainty(&normalized).is_some() => {
+            let n = normalized.parse::<N>().map_err(|_| invalid())?;
+            return Ok((n.scaled_exactly(&factor).ok_or_else(inexact)?, key, places));
+        }
+        // Sizes and units are scaled exactly, so 1.5K is 1536 and 3.2kg is 3200 g even in the
+        // integer mode
+        Some(factor) => Some(
+            parse_exact(&normalized, format.percent_points)
+                .unwrap_or_else(|| normalized.parse())
+                .map(|n| &n * &factor),
+        ),
+        None => parse_exact(&normalized, format.percent_points),
+    };
+
+    let exact = match exact {
+        Some(exact) => exact.map_err(|_| invalid())?,
+        None => match normalized.parse::<N>() {
+            Ok(n) => return Ok((n, key, places)),
+            // The integer mode reads scientific notation such as `6.02E23` if it is an integer
+            Err(_) if normalized.contains(['e', 'E']) => normalized
+                .parse::<Decimal>()
+                .map(Rational::from)
+                .map_err(|_| invalid())?,
+            Err(_) => return Err(invalid()),
+        },
+    };
+    let n = N::from_rational(&exact).ok_or_else(inexact)?;
+    Ok((n, key, places))
+}
 
-    Ok(parse_results.into_iter().flatten().collect())
+/// Value of `text` if it is a number in words, such as `twelve` or `3 million`, with its numerals
+/// read in `format`.
+fn read_words(text: &str, format: &NumberFormat) -> Option<Rational> {
+    parse_words(text, |numeral| format.normalize(numeral).ok()?.parse().ok())
 }
 
 fn print_help() {
@@ -108,12 +1368,62 @@ fn print_help() {
 mod test {
     use super::*;
 
+    fn decimals(nums: &[&str]) -> Vec<Decimal> {
+        nums.iter().map(|n| n.parse().unwrap()).collect()
+    }
+
+    fn parse_nums<N: Number>(num_str: &str) -> Result<Vec<N>, String> {
+        Tokenizer::new(num_str.as_bytes(), Delimiter::Whitespace)
+            .map(|token| {
+                let token = token.map_err(|e| e.to_string())?;
+                parse_num_str(&token.text, &NumberFormat::default()).map(|(n, _, _)| n)
+            })
+            .collect()
+    }
+
+    fn total<A: Accumulator + Clone>(num_str: &str, sum: A) -> Result<String, Error> {
+        total_with(num_str, sum, &Parsing::default())
+    }
+
+    fn total_in<A: Accumulator + Clone>(
+        num_str: &str,
+        sum: A,
+        format: &NumberFormat,
+    ) -> Result<String, Error> {
+        let parsing = Parsing {
+            format: format.clone(),
+            ..Default::default()
+        };
+        total_with(num_str, sum, &parsing)
+    }
+
+    fn total_with<A: Accumulator + Clone>(
+        num_str: &str,
+        sum: A,
+        parsing: &Parsing,
+    ) -> Result<String, Error> {
+        total_printed(num_str, sum, parsing, &Printing::default())
+    }
+
+    fn total_printed<A: Accumulator + Clone>(
+        num_str: &str,
+        sum: A,
+        parsing: &Parsing,
+        printing: &Printing,
+    ) -> Result<String, Error> {
+        sum_num_str(num_str.as_bytes(), "<arguments>", sum, parsing, |_| {})
+            .map(|(totals, _)| format_totals(&totals, printing))
+    }
+
     #[test]
     fn can_parse_cli_arg_config() {
         let args = vec!["./rsum".to_owned(), "1 2 3".to_owned()];
 
         let parsed_config = parse_args(args);
-        let expected = Config::CliArg("1 2 3".to_owned());
+        let expected = Config::Sum(Box::new(Options {
+            input: Input::CliArg("1 2 3".to_owned()),
+            ..Default::default()
+        }));
 
         assert_eq!(parsed_config, Ok(expected));
     }
@@ -127,7 +1437,10 @@ mod test {
         ];
 
         let parsed_config = parse_args(args);
-        let expected = Config::File(PathBuf::from("numbers.txt".to_owned()));
+        let expected = Config::Sum(Box::new(Options {
+            input: Input::File(PathBuf::from("numbers.txt".to_owned())),
+            ..Default::default()
+        }));
 
         assert_eq!(parsed_config, Ok(expected));
     }
@@ -143,18 +1456,362 @@ mod test {
     }
 
     #[test]
-    #[ignore = "Not sure how to implement this test yet."]
     fn can_parse_stdin_config() {
-        unimplemented!()
-    }
+        let args = vec!["./rsum".to_owned()];
 
-    #[test]
-    fn can_parse_num_str_without_commas() {
-        let num_str = "0.1 10 20.5 30000 40.".to_owned();
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::default());
 
-        let nums = parse_num_str(num_str);
+        assert_eq!(parsed_config, Ok(expected));
+    }
 
-        let expected = vec![0.1, 10., 20.5, 30_000., 40.];
+    #[test]
+    fn can_parse_mode_config() {
+        let args = vec![
+            "./rsum".to_owned(),
+            "--mode".to_owned(),
+            "rational".to_owned(),
+            "1/3".to_owned(),
+        ];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            input: Input::CliArg("1/3".to_owned()),
+            mode: Mode::Rational,
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_inline_mode_config_after_file() {
+        let args = vec![
+            "./rsum".to_owned(),
+            "-f".to_owned(),
+            "numbers.txt".to_owned(),
+            "--mode=f64".to_owned(),
+        ];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            input: Input::File(PathBuf::from("numbers.txt".to_owned())),
+            mode: Mode::F64,
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_algorithm_config() {
+        let args = vec![
+            "./rsum".to_owned(),
+            "-m".to_owned(),
+            "f32".to_owned(),
+            "--algorithm=kahan".to_owned(),
+            "-e".to_owned(),
+        ];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            mode: Mode::F32,
+            algorithm: Algorithm::Kahan,
+            error_bound: true,
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_threads_config() {
+        let args = vec!["./rsum".to_owned(), "--threads=4".to_owned()];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            threads: Some(4),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+
+        let args = vec!["./rsum".to_owned(), "-j".to_owned(), "0".to_owned()];
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn can_parse_max_errors_config() {
+        let args = vec![
+            "./rsum".to_owned(),
+            "--max-errors".to_owned(),
+            "0".to_owned(),
+        ];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            max_errors: Some(0),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_number_format_config() {
+        let args = vec![
+            "./rsum".to_owned(),
+            "--locale=de_DE.UTF-8".to_owned(),
+            "--decimal".to_owned(),
+            ".".to_owned(),
+        ];
+
+        let Ok(Config::Sum(options)) = parse_args(args) else {
+            panic!("expected options");
+        };
+        let format = options.number_format();
+        assert_eq!((format.decimal, format.grouping), ('.', vec![',']));
+
+        let args = vec!["./rsum".to_owned(), "--locale=xx".to_owned()];
+        assert!(parse_args(args).is_err());
+
+        let args = vec!["./rsum".to_owned(), "--group=1".to_owned()];
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn can_parse_delimiter_config() {
+        let args = vec!["./rsum".to_owned(), "-d".to_owned(), "\\t".to_owned()];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            delimiter: Delimiter::Literal("\t".to_owned()),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+
+        let args = vec!["./rsum".to_owned(), "--delimiter=".to_owned()];
+        assert!(parse_args(args).is_err());
+
+        let args = vec!["./rsum".to_owned(), "--delimiter-regex=(,".to_owned()];
+        assert!(parse_args(args).is_err());
+
+        let args = vec!["./rsum".to_owned(), "--delimiter-regex=".to_owned()];
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn can_parse_conversion_config() {
+        let args = ["./rsum", "--to", "€", "--rates", "rates.csv"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            to: Some("EUR"),
+            rates: Some(PathBuf::from("rates.csv")),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+        assert!(parse_args(args[..3].to_vec()).is_err());
+        assert!(parse_args(vec!["./rsum".to_owned(), "--to=XYZ".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn can_parse_output_radix_config() {
+        let args = ["./rsum", "-m", "integer", "--output-radix", "hex"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            mode: Mode::Integer,
+            output_radix: Some(16),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+        assert!(parse_args(vec!["./rsum".to_owned(), "--output-radix=2".to_owned()]).is_err());
+
+        let args = ["./rsum", "-m", "integer", "--output-radix", "3"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+    }
+
+    #[test]
+    fn can_parse_size_style_config() {
+        let args = ["./rsum", "--si"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            size_style: Some(SizeStyle::Si),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+
+        let args = ["./rsum", "--sizes"].map(str::to_owned);
+        let Ok(Config::Sum(options)) = parse_args(args.to_vec()) else {
+            panic!("expected options");
+        };
+        assert!(options.number_format().sizes);
+
+        let args = [
+            "./rsum",
+            "-m",
+            "integer",
+            "--output-radix",
+            "hex",
+            "--human-readable",
+        ];
+        assert!(parse_args(args.map(str::to_owned).to_vec()).is_err());
+    }
+
+    #[test]
+    fn can_parse_duration_config() {
+        let args = ["./rsum", "-m", "duration", "--duration-format", "iso"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            mode: Mode::Duration,
+            duration_format: Some(DurationFormat::Iso),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+        let args = ["./rsum", "--duration-format", "iso"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+
+        let args = ["./rsum", "-m", "duration", "--duration-format", "hms"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+    }
+
+    #[test]
+    fn can_parse_precision_config() {
+        let args = ["./rsum", "--precision", "inputs", "--rounding", "floor"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            precision: Some(Precision::Inputs),
+            rounding: Some(Rounding::Floor),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+        let args = ["./rsum", "--precision", "2"].map(str::to_owned);
+        let expected = Config::Sum(Box::new(Options {
+            precision: Some(Precision::Places(2)),
+            ..Default::default()
+        }));
+        assert_eq!(parse_args(args.to_vec()), Ok(expected));
+
+        for args in [
+            &["./rsum", "--rounding", "ceil"][..],
+            &["./rsum", "--precision", "-1"],
+            &["./rsum", "--precision", "2", "--rounding", "up"],
+            &["./rsum", "--precision", "2", "--si"],
+            &["./rsum", "-m", "duration", "--precision", "2"],
+        ] {
+            let args = args.iter().map(|&arg| arg.to_owned()).collect();
+            assert!(parse_args(args).is_err());
+        }
+    }
+
+    #[test]
+    fn can_parse_uncertainty_config() {
+        let args = ["./rsum", "-m", "uncertainty", "--correlated"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            mode: Mode::Uncertainty,
+            correlated: true,
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+        let args = ["./rsum", "--correlated"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+    }
+
+    #[test]
+    fn can_parse_unit_config() {
+        let args = ["./rsum", "--unit", "kg"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            unit: unit_of("kg"),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+        assert!(parse_args(vec!["./rsum".to_owned(), "--unit=kgs".to_owned()]).is_err());
+
+        let args = ["./rsum", "--units"].map(str::to_owned);
+        let expected = Config::Sum(Box::new(Options {
+            units: true,
+            ..Default::default()
+        }));
+        assert_eq!(parse_args(args.to_vec()), Ok(expected));
+
+        let args = ["./rsum", "-m", "duration", "--units"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+    }
+
+    #[test]
+    fn can_parse_non_finite_config() {
+        let args = ["./rsum", "-m", "f64", "--non-finite", "skip"].map(str::to_owned);
+
+        let parsed_config = parse_args(args.to_vec());
+        let expected = Config::Sum(Box::new(Options {
+            mode: Mode::F64,
+            non_finite: NonFinite::Skip,
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+
+        let args = ["./rsum", "--non-finite", "propagate"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+
+        let args = ["./rsum", "-m", "f64", "--non-finite", "ignore"].map(str::to_owned);
+        assert!(parse_args(args.to_vec()).is_err());
+    }
+
+    #[test]
+    fn rejects_algorithm_for_exact_modes() {
+        let args = vec!["./rsum".to_owned(), "-a".to_owned(), "pairwise".to_owned()];
+        assert!(parse_args(args).is_err());
+
+        let args = vec!["./rsum".to_owned(), "--error-bound".to_owned()];
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_mode_and_options() {
+        let args = vec!["./rsum".to_owned(), "-m".to_owned(), "f16".to_owned()];
+        assert!(parse_args(args).is_err());
+
+        let args = vec!["./rsum".to_owned(), "--verbose".to_owned()];
+        assert!(parse_args(args).is_err());
+    }
+
+    #[test]
+    fn can_parse_negative_numbers_as_cli_args() {
+        let args = vec!["./rsum".to_owned(), "-5".to_owned(), "3".to_owned()];
+
+        let parsed_config = parse_args(args);
+        let expected = Config::Sum(Box::new(Options {
+            input: Input::CliArg("-5 3".to_owned()),
+            ..Default::default()
+        }));
+
+        assert_eq!(parsed_config, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_num_str_without_commas() {
+        let num_str = "0.1 10 20.5 30000 40.".to_owned();
+
+        let nums = parse_nums(&num_str);
+
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
 
         assert_eq!(nums, Ok(expected));
     }
@@ -163,9 +1820,9 @@ mod test {
     fn can_parse_num_str_with_commas() {
         let num_str = "0.1 10 20.5 30,000 40.".to_owned();
 
-        let nums = parse_num_str(num_str);
+        let nums = parse_nums(&num_str);
 
-        let expected = vec![0.1, 10., 20.5, 30_000., 40.];
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
 
         assert_eq!(nums, Ok(expected));
     }
@@ -174,9 +1831,9 @@ mod test {
     fn can_parse_num_str_space_delimited() {
         let num_str = "0.1 10 20.5 30,000 40.".to_owned();
 
-        let nums = parse_num_str(num_str);
+        let nums = parse_nums(&num_str);
 
-        let expected = vec![0.1, 10., 20.5, 30_000., 40.];
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
 
         assert_eq!(nums, Ok(expected));
     }
@@ -185,9 +1842,9 @@ mod test {
     fn can_parse_num_str_newline_delimited() {
         let num_str = "0.1\n10\n20.5\n30,000\n40.".to_owned();
 
-        let nums = parse_num_str(num_str);
+        let nums = parse_nums(&num_str);
 
-        let expected = vec![0.1, 10., 20.5, 30_000., 40.];
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
 
         assert_eq!(nums, Ok(expected));
     }
@@ -196,10 +1853,882 @@ mod test {
     fn can_parse_num_str_space_and_newline_delimited() {
         let num_str = "0.1 10\n20.5 30,000\n40.".to_owned();
 
-        let nums = parse_num_str(num_str);
+        let nums = parse_nums(&num_str);
+
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
+
+        assert_eq!(nums, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_num_str_tab_delimited() {
+        let num_str = "0.1\t10\t\t20.5\n30,000\t40.".to_owned();
+
+        let nums = parse_nums(&num_str);
+
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
+
+        assert_eq!(nums, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_num_str_with_crlf_line_endings() {
+        let num_str = "0.1 10\r\n20.5\r\n\r\n30,000 40.\r\n".to_owned();
+
+        let nums = parse_nums(&num_str);
 
-        let expected = vec![0.1, 10., 20.5, 30_000., 40.];
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
 
         assert_eq!(nums, Ok(expected));
     }
+
+    #[test]
+    fn can_parse_num_str_with_consecutive_spaces() {
+        let num_str = "  0.1    10\n\n   20.5 30,000  40.  ".to_owned();
+
+        let nums = parse_nums(&num_str);
+
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
+
+        assert_eq!(nums, Ok(expected));
+    }
+
+    #[test]
+    fn can_parse_num_str_unicode_whitespace_delimited() {
+        let num_str = "0.1\u{A0}10\u{3000}20.5\u{2009}30,000\u{2028}40.".to_owned();
+
+        let nums = parse_nums(&num_str);
+
+        let expected = decimals(&["0.1", "10", "20.5", "30000", "40"]);
+
+        assert_eq!(nums, Ok(expected));
+    }
+
+    #[test]
+    fn keeps_locale_grouping_spaces_within_numbers() {
+        let french = NumberFormat::from_locale("fr_FR").unwrap();
+
+        assert_eq!(
+            total_in("1\u{202F}000,5\t2", ExactSum::<Decimal>::default(), &french),
+            Ok("1002.5".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_sum_num_str_as_decimal() {
+        let num_str = "1,000.25\n250.25\n125.25\n0.25\n16777217";
+
+        let sum = total(num_str, ExactSum::<Decimal>::default());
+
+        assert_eq!(sum, Ok("16778593.00".to_owned()));
+    }
+
+    #[test]
+    fn can_sum_num_str_as_integer() {
+        let num_str = "170141183460469231731687303715884105727 1 1,000";
+
+        let sum = total(num_str, ExactSum::<Integer>::default());
+
+        assert_eq!(
+            sum,
+            Ok("170141183460469231731687303715884106728".to_owned())
+        );
+        assert!(total("1 2.5", ExactSum::<Integer>::default()).is_err());
+    }
+
+    #[test]
+    fn can_sum_num_str_as_rational() {
+        let num_str = "1/3 1/3 1/3";
+
+        let sum = total(num_str, ExactSum::<Rational>::default());
+
+        assert_eq!(sum, Ok("1".to_owned()));
+        assert_eq!(
+            total("0.5 1/3", ExactSum::<Rational>::default()),
+            Ok("5/6".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_sum_fractions_and_percentages() {
+        let num_str = "3/4 ½ 2⅓\n15% -1/8";
+
+        assert_eq!(
+            total(num_str, ExactSum::<Rational>::default()),
+            Ok("433/120".to_owned())
+        );
+        assert_eq!(
+            total("3/4 12.5% 1½", ExactSum::<Decimal>::default()),
+            Ok("2.375".to_owned())
+        );
+        assert_eq!(
+            total("6/3 200%", ExactSum::<Integer>::default()),
+            Ok("4".to_owned())
+        );
+
+        let Err(Error::Parse(e)) = total("1/3", ExactSum::<Decimal>::default()) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.errors[0].message,
+            "Failed to parse '1/3': it can't be held exactly in this mode, but can in --mode rational"
+        );
+    }
+
+    #[test]
+    fn can_sum_mixed_numbers_and_percentage_points() {
+        let format = NumberFormat {
+            mixed_numbers: true,
+            percent_points: true,
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(
+            total_in(
+                "1 1/2 2\n-2 ¼ 3/4 10%",
+                ExactSum::<Rational>::default(),
+                &format
+            ),
+            Ok("12".to_owned())
+        );
+        assert_eq!(
+            total_in(
+                "1 1/2",
+                ExactSum::<Rational>::default(),
+                &NumberFormat::default()
+            ),
+            Ok("3/2".to_owned())
+        );
+
+        // A sign alone isn't a whole number
+        let Err(Error::Parse(e)) = total_in("- 1/2", ExactSum::<Rational>::default(), &format)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(e.errors[0].token, "-");
+    }
+
+    #[test]
+    fn can_sum_numbers_in_words() {
+        let format = NumberFormat {
+            words: true,
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(
+            total_in(
+                "twelve one hundred and five\nOne two 3 million 2.5k $4 thousand",
+                ExactSum::<Integer>::default(),
+                &format
+            ),
+            Ok("3002620\n4000 USD".to_owned())
+        );
+        assert_eq!(
+            total_in("1.5 thousand", ExactSum::<Integer>::default(), &format),
+            Ok("1500".to_owned())
+        );
+
+        let Err(Error::Parse(e)) =
+            total_in("five and six", ExactSum::<Decimal>::default(), &format)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(e.errors[0].message, "Failed to parse 'and'");
+        assert!(total("twelve", ExactSum::<Decimal>::default()).is_err());
+    }
+
+    #[test]
+    fn can_sum_num_str_as_floats() {
+        let num_str = "0.1 0.2";
+
+        assert_eq!(
+            total(num_str, FloatSum::<f64>::new(Algorithm::Naive)),
+            Ok("0.30000000000000004".to_owned())
+        );
+        assert_eq!(
+            total(num_str, FloatSum::<f32>::new(Algorithm::Naive)),
+            Ok("0.3".to_owned())
+        );
+        assert_eq!(
+            total("16777216 1", FloatSum::<f32>::new(Algorithm::Naive)),
+            Ok("16777216".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_sum_num_str_with_compensated_algorithms() {
+        let num_str = "1 1e100 1 -1e100";
+
+        assert_eq!(
+            total(num_str, FloatSum::<f64>::new(Algorithm::Naive)),
+            Ok("0".to_owned())
+        );
+        assert_eq!(
+            total(num_str, FloatSum::<f64>::new(Algorithm::Neumaier)),
+            Ok("2".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_sum_num_str_in_locale_format() {
+        let german = NumberFormat::from_locale("de_DE").unwrap();
+
+        assert_eq!(
+            total_in("1.234.567,89 12,5", ExactSum::<Decimal>::default(), &german),
+            Ok("1234580.39".to_owned())
+        );
+        assert_eq!(
+            total_in(
+                "12,5",
+                ExactSum::<Decimal>::default(),
+                &NumberFormat::default()
+            ),
+            Ok("125".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_misplaced_grouping_when_strict() {
+        let strict = NumberFormat {
+            strict: true,
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(
+            total_in("1,000 2,500.5", ExactSum::<Decimal>::default(), &strict),
+            Ok("3500.5".to_owned())
+        );
+        let Err(Error::Parse(e)) = total_in("1 1,00", ExactSum::<Decimal>::default(), &strict)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.errors[0].message,
+            "Failed to parse '1,00': grouping ',' at character 2 should be followed by 3 digits"
+        );
+        assert_eq!((e.errors[0].line, e.errors[0].column), (1, 3));
+    }
+
+    #[test]
+    fn can_sum_num_str_split_on_a_delimiter() {
+        let comma = Parsing {
+            delimiter: Delimiter::Literal(",".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            total_with("3,4,5\n1.5, 2", ExactSum::<Decimal>::default(), &comma),
+            Ok("15.5".to_owned())
+        );
+
+        let regex = Parsing {
+            delimiter: Delimiter::Regex(r"[;|]".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(
+            total_with("1,000;2|3", ExactSum::<Decimal>::default(), &regex),
+            Ok("1005".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_sum_num_str_in_accounting_notation() {
+        let accounting = NumberFormat {
+            accounting: true,
+            ..NumberFormat::default()
+        };
+        let num_str = "1,000.00\n(1,234.56)\n200.50-\n\u{2212}12 15 DR\n1,234.56 CR\n";
+
+        assert_eq!(
+            total_in(num_str, ExactSum::<Decimal>::default(), &accounting),
+            Ok("-1666.62".to_owned())
+        );
+        assert!(total_in(
+            "(12)",
+            ExactSum::<Decimal>::default(),
+            &NumberFormat::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reports_accounting_suffix_with_its_number() {
+        let accounting = NumberFormat {
+            accounting: true,
+            ..NumberFormat::default()
+        };
+
+        let Err(Error::Parse(e)) =
+            total_in("1\n1.2.3 CR", ExactSum::<Decimal>::default(), &accounting)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(e.errors[0].token, "1.2.3 CR");
+        assert_eq!(e.errors[0].line_text.as_deref(), Some("1.2.3 CR"));
+    }
+
+    #[test]
+    fn can_sum_num_str_per_currency() {
+        let num_str = "$1,200.50 €30\n12.00 USD\nJPY 5000 7\n-£3 2.5 €";
+
+        assert_eq!(
+            total(num_str, ExactSum::<Decimal>::default()),
+            Ok("7\n32.5 EUR\n-3 GBP\n5000 JPY\n1212.50 USD".to_owned())
+        );
+        assert_eq!(
+            total("$5 $2.50", ExactSum::<Decimal>::default()),
+            Ok("7.50 USD".to_owned())
+        );
+        assert_eq!(
+            total("€30 JPY 5000", ExactSum::<Decimal>::default()),
+            Ok("30 EUR\n5000 JPY".to_owned())
+        );
+
+        let skipping = Parsing {
+            on_invalid: OnInvalid::Skip { list: false },
+            ..Default::default()
+        };
+        assert_eq!(
+            total_with(
+                "Paid USD 5 today",
+                ExactSum::<Decimal>::default(),
+                &skipping
+            ),
+            Ok("5 USD".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_mixed_currencies_when_single_currency() {
+        let single = Parsing {
+            single_currency: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            total_with("$5 3 USD 2", ExactSum::<Decimal>::default(), &single),
+            Ok("2\n8 USD".to_owned())
+        );
+
+        let Err(Error::Parse(e)) = total_with("$5\n3 €30", ExactSum::<Decimal>::default(), &single)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.errors[0].message,
+            "'€30' is in EUR, but earlier amounts are in USD"
+        );
+        assert_eq!((e.errors[0].line, e.errors[0].column), (2, 3));
+    }
+
+    #[test]
+    fn converts_subtotals_to_one_currency() {
+        let path = env::temp_dir().join(format!("rsum-rates-{}.csv", process::id()));
+        std::fs::write(&path, "EUR,1\nUSD,1.08\nJPY,160\n").unwrap();
+        let conversion = Conversion {
+            to: "USD",
+            rates: Rates::from_file(&path).unwrap(),
+        };
+        std::fs::remove_file(&path).unwrap();
+
+        let parsing = Parsing {
+            conversion: Some(conversion.clone()),
+            ..Default::default()
+        };
+        let printing = Printing {
+            conversion: Some(&conversion),
+            ..Printing::default()
+        };
+        let sum =
+            |num_str| total_printed(num_str, ExactSum::<Decimal>::default(), &parsing, &printing);
+
+        assert_eq!(
+            sum("$1,200.50 €30 JPY 5000 7"),
+            Ok("7\n1266.65 USD".to_owned())
+        );
+        assert_eq!(sum("€0.01 €0.01"), Ok("0.02 USD".to_owned()));
+
+        let rounded = Printing {
+            precision: Some(Precision::Inputs),
+            ..printing
+        };
+        assert_eq!(
+            total_printed(
+                "$1,200.50 €30 JPY 5000",
+                ExactSum::<Decimal>::default(),
+                &parsing,
+                &rounded
+            ),
+            Ok("1267 USD".to_owned())
+        );
+
+        let Err(Error::Parse(e)) = sum("$1 £3") else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.errors[0].message,
+            "There is no exchange rate to convert '£3' from GBP"
+        );
+    }
+
+    #[test]
+    fn calls_on_line_end_after_each_line_with_numbers() {
+        let num_str = "1 2\n\n3 \n4";
+        let mut running = Vec::new();
+
+        let sum = sum_num_str(
+            num_str.as_bytes(),
+            "<arguments>",
+            ExactSum::<Decimal>::default(),
+            &Parsing::default(),
+            |totals| running.push(format_totals(totals, &Printing::default())),
+        );
+
+        assert!(sum.is_ok());
+        assert_eq!(running, vec!["3", "6", "10"]);
+    }
+
+    #[test]
+    fn reports_location_of_unparsable_token() {
+        let num_str = "1 2\n3 1,2.3.4 5";
+
+        let sum = total(num_str, ExactSum::<Decimal>::default());
+        let expected = ParseError {
+            message: "Failed to parse '1,2.3.4'".to_owned(),
+            source: "<arguments>".to_owned(),
+            line: 2,
+            column: 3,
+            token: "1,2.3.4".to_owned(),
+            line_text: Some("3 1,2.3.4 5".to_owned()),
+        };
+
+        assert_eq!(sum, Err(Error::from(expected)));
+        assert_eq!(sum.unwrap_err().exit_code(), 65);
+    }
+
+    #[test]
+    fn collects_unparsable_tokens_up_to_the_limit() {
+        let num_str = "1 a 2\nb\n3 c";
+        let locations = |max_errors| {
+            let parsing = Parsing {
+                on_invalid: OnInvalid::Fail(max_errors),
+                ..Default::default()
+            };
+            let Err(Error::Parse(e)) =
+                total_with(num_str, ExactSum::<Decimal>::default(), &parsing)
+            else {
+                panic!("expected a parse error");
+            };
+            e.errors
+                .iter()
+                .map(|e| (e.token.clone(), e.line, e.column))
+                .collect::<Vec<_>>()
+        };
+
+        let all = vec![
+            ("a".to_owned(), 1, 3),
+            ("b".to_owned(), 2, 1),
+            ("c".to_owned(), 3, 3),
+        ];
+        assert_eq!(locations(usize::MAX), all);
+        assert_eq!(locations(2), all[..2]);
+    }
+
+    #[test]
+    fn skips_unparsable_tokens_when_lenient() {
+        let num_str = "Total: 12 items\n3 more";
+        let skip = |list| {
+            sum_num_str(
+                num_str.as_bytes(),
+                "<arguments>",
+                ExactSum::<Decimal>::default(),
+                &Parsing {
+                    on_invalid: OnInvalid::Skip { list },
+                    ..Default::default()
+                },
+                |_| {},
+            )
+            .unwrap()
+        };
+
+        let (sum, skipped) = skip(false);
+        assert_eq!(format_totals(&sum, &Printing::default()), "15");
+        assert_eq!((skipped.count, skipped.tokens.len()), (3, 0));
+
+        let (_, skipped) = skip(true);
+        let locations = skipped
+            .tokens
+            .iter()
+            .map(|token| (token.token.as_str(), token.line, token.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locations,
+            [("Total:", 1, 1), ("items", 1, 11), ("more", 2, 3)]
+        );
+    }
+
+    #[test]
+    fn can_sum_radix_literals_and_print_in_a_radix() {
+        let num_str = "0xFF 0o10 0b11\n-0x1 1_000";
+
+        assert_eq!(
+            total(num_str, ExactSum::<Integer>::default()),
+            Ok("1265".to_owned())
+        );
+        assert_eq!(
+            total("+0x10 0.5 -0b1", ExactSum::<Decimal>::default()),
+            Ok("15.5".to_owned())
+        );
+        assert!(total("0x1.8", ExactSum::<Decimal>::default()).is_err());
+
+        for (radix, expected) in [
+            (16, "-0x100"),
+            (8, "-0o400"),
+            (2, "-0b100000000"),
+            (10, "-256"),
+        ] {
+            let printing = Printing {
+                radix: Some(radix),
+                ..Printing::default()
+            };
+            assert_eq!(
+                total_printed(
+                    "0xFF -0x1FF",
+                    ExactSum::<Integer>::default(),
+                    &Parsing::default(),
+                    &printing
+                ),
+                Ok(expected.to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn can_sum_sizes_and_print_them_human_readable() {
+        let num_str = "1.5K 200MiB\n3G 12kB 512B";
+        let sizes = NumberFormat {
+            sizes: true,
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(
+            total_in(num_str, ExactSum::<Integer>::default(), &sizes),
+            Ok("3430954720".to_owned())
+        );
+        assert_eq!(
+            total_in("1.5K", ExactSum::<Decimal>::default(), &sizes),
+            Ok("1536".to_owned())
+        );
+        assert_eq!(
+            total_in("512 B 2 KiB\n1 KB", ExactSum::<Integer>::default(), &sizes),
+            Ok("3560".to_owned())
+        );
+        assert!(total_in("1.1K", ExactSum::<Integer>::default(), &sizes).is_err());
+        assert!(total("1E 2", ExactSum::<Decimal>::default()).is_err());
+
+        let words = NumberFormat {
+            words: true,
+            ..sizes.clone()
+        };
+        assert_eq!(
+            total_in("2k 1K 1M", ExactSum::<Decimal>::default(), &words),
+            Ok("1051600".to_owned())
+        );
+
+        let parsing = Parsing {
+            format: sizes,
+            ..Parsing::default()
+        };
+
+        for (style, expected) in [(SizeStyle::Iec, "3.2G"), (SizeStyle::Si, "3.4GB")] {
+            let printing = Printing {
+                size_style: Some(style),
+                ..Printing::default()
+            };
+            assert_eq!(
+                total_printed(num_str, ExactSum::<Integer>::default(), &parsing, &printing),
+                Ok(expected.to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn can_sum_amounts_with_units() {
+        let units = NumberFormat {
+            units: true,
+            ..NumberFormat::default()
+        };
+
+        assert_eq!(
+            total_in("3.2kg 450g", ExactSum::<Decimal>::default(), &units),
+            Ok("3.65 kg".to_owned())
+        );
+        assert_eq!(
+            total_in("12m\n5 km 2\n1 ft", ExactSum::<Decimal>::default(), &units),
+            Ok("2\n5.0123048 km".to_owned())
+        );
+        assert_eq!(
+            total_in("3.2kg 450g", ExactSum::<Integer>::default(), &units),
+            Ok("3650 g".to_owned())
+        );
+        assert!(total("3.2kg", ExactSum::<Decimal>::default()).is_err());
+
+        let Err(Error::Parse(e)) = total_in("12m 5s", ExactSum::<Decimal>::default(), &units)
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.errors[0].message,
+            "'5s' is a time, which can't be added to the length of earlier amounts"
+        );
+
+        // Units written apart are only joined to a number, and words only at the end of a phrase
+        assert_eq!(
+            total_in("450 g 1e3g", ExactSum::<Decimal>::default(), &units),
+            Ok("1.45 kg".to_owned())
+        );
+        assert_eq!(
+            total_in("12 m\n3 in 1µm", ExactSum::<Decimal>::default(), &units),
+            Ok("12.076201 m".to_owned())
+        );
+        let skipping = Parsing {
+            format: units.clone(),
+            on_invalid: OnInvalid::Skip { list: false },
+            ..Parsing::default()
+        };
+        assert_eq!(
+            total_with(
+                "I have 3 in stock and 4 more",
+                ExactSum::<Decimal>::default(),
+                &skipping
+            ),
+            Ok("7".to_owned())
+        );
+        assert_eq!(
+            total_with("1 2 h x3 m", ExactSum::<Decimal>::default(), &skipping),
+            Ok("1\n2 h".to_owned())
+        );
+
+        let parsing = Parsing {
+            format: units,
+            unit: unit_of("ft"),
+            ..Parsing::default()
+        };
+        let printing = Printing {
+            unit: unit_of("ft"),
+            ..Printing::default()
+        };
+        assert_eq!(
+            total_printed(
+                "1m 1 ft",
+                ExactSum::<Decimal>::default(),
+                &parsing,
+                &printing
+            ),
+            Ok("4.280840 ft".to_owned())
+        );
+        assert!(total_with("1 kg", ExactSum::<Decimal>::default(), &parsing).is_err());
+        assert_eq!(
+            total_printed("", ExactSum::<Decimal>::default(), &parsing, &printing),
+            Ok("0 ft".to_owned())
+        );
+    }
+
+    #[test]
+    fn reads_scientific_notation_in_every_mode() {
+        let num_str = "1e-3 6.02E23 -2.5e+1";
+
+        assert_eq!(
+            total(num_str, ExactSum::<Decimal>::default()),
+            Ok("601999999999999999999975.001".to_owned())
+        );
+        assert_eq!(
+            total(num_str, ExactSum::<Rational>::default()),
+            Ok("601999999999999999999975001/1000".to_owned())
+        );
+        assert_eq!(
+            total("1e-3 2.5E2 -1e+1", FloatSum::<f64>::new(Algorithm::Naive)),
+            Ok("240.001".to_owned())
+        );
+        assert_eq!(
+            total("6.02E23 1e3", ExactSum::<Integer>::default()),
+            Ok("602000000000000000001000".to_owned())
+        );
+
+        let Err(Error::Parse(e)) = total("1e-3", ExactSum::<Integer>::default()) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            e.errors[0].message,
+            "Failed to parse '1e-3': it can't be held exactly in this mode, but can in --mode rational"
+        );
+        assert!(total("inf", ExactSum::<Decimal>::default()).is_err());
+    }
+
+    #[test]
+    fn handles_non_finite_values_by_policy() {
+        let num_str = "1 inf\n-Infinity NaN 2";
+        let sum = || FloatSum::<f64>::new(Algorithm::Naive);
+        let with_policy = |non_finite| Parsing {
+            non_finite,
+            ..Parsing::default()
+        };
+
+        let Err(Error::Parse(e)) = total(num_str, sum()) else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(
+            (
+                e.errors[0].line,
+                e.errors[0].column,
+                e.errors[0].message.as_str()
+            ),
+            (
+                1,
+                3,
+                "'inf' isn't a finite number, which --non-finite can skip or add"
+            )
+        );
+
+        let (totals, skipped) = sum_num_str(
+            num_str.as_bytes(),
+            "<arguments>",
+            sum(),
+            &with_policy(NonFinite::Skip),
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(format_totals(&totals, &Printing::default()), "3");
+        assert_eq!((skipped.non_finite, skipped.count), (3, 0));
+
+        assert_eq!(
+            total_with("1 inf", sum(), &with_policy(NonFinite::Propagate)),
+            Ok("inf".to_owned())
+        );
+        assert_eq!(
+            total_with(num_str, sum(), &with_policy(NonFinite::Propagate)),
+            Ok("NaN".to_owned())
+        );
+    }
+
+    #[test]
+    fn can_sum_durations() {
+        let num_str = "00:45:10 1h30m15s\n90s PT1H30M -0:00:10.5";
+        let parsing = Parsing::default();
+
+        assert_eq!(
+            total_with(num_str, ExactSum::<Duration>::default(), &parsing),
+            Ok("3:46:44.5".to_owned())
+        );
+
+        for (format, expected) in [
+            (DurationFormat::Go, "3h46m44.5s"),
+            (DurationFormat::Iso, "PT3H46M44.5S"),
+            (DurationFormat::Seconds, "13604.5"),
+        ] {
+            let printing = Printing {
+                duration_format: Some(format),
+                ..Printing::default()
+            };
+            assert_eq!(
+                total_printed(
+                    num_str,
+                    ExactSum::<Duration>::default(),
+                    &parsing,
+                    &printing
+                ),
+                Ok(expected.to_owned())
+            );
+        }
+    }
+
+    #[test]
+    fn rounds_printed_totals_to_a_precision() {
+        let rounded = |num_str, precision, rounding| {
+            let printing = Printing {
+                precision: Some(precision),
+                rounding,
+                ..Printing::default()
+            };
+            total_printed(
+                num_str,
+                FloatSum::<f32>::new(Algorithm::Naive),
+                &Parsing {
+                    format: NumberFormat {
+                        units: true,
+                        ..NumberFormat::default()
+                    },
+                    ..Parsing::default()
+                },
+                &printing,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            rounded(
+                "1000.25 250.25 125.25 0.2499",
+                Precision::Places(2),
+                Rounding::HalfEven
+            ),
+            "1376.00"
+        );
+        assert_eq!(
+            rounded("1.25 3.1", Precision::Inputs, Rounding::HalfEven),
+            "4.4"
+        );
+        assert_eq!(
+            rounded("1.25 3.17 1/2", Precision::Inputs, Rounding::Truncate),
+            "4.92"
+        );
+        assert_eq!(
+            rounded("$1.999 €2 $2.5 1e-3", Precision::Inputs, Rounding::Ceil),
+            "0.001\n2 EUR\n4.5 USD"
+        );
+        assert_eq!(
+            rounded("3.2kg 450g", Precision::Inputs, Rounding::HalfEven),
+            "3.6 kg"
+        );
+        assert_eq!(
+            rounded("1.25kg 3.1kg", Precision::Inputs, Rounding::HalfEven),
+            "4.4 kg"
+        );
+        assert_eq!(
+            rounded("250mg 1.5g", Precision::Inputs, Rounding::HalfEven),
+            "1.8 g"
+        );
+    }
+
+    #[test]
+    fn can_sum_measurements_with_uncertainties() {
+        let num_str = "1.5±0.3 2.25 +/- 0.4\n1/4 0.5 ± 0.1 kg";
+        let parsing = Parsing {
+            format: NumberFormat {
+                uncertainties: true,
+                units: true,
+                ..NumberFormat::default()
+            },
+            ..Parsing::default()
+        };
+
+        assert_eq!(
+            total_with(num_str, ExactSum::<Uncertain>::default(), &parsing),
+            Ok("4.00 ± 0.50\n500 ± 100 g".to_owned())
+        );
+
+        let printing = Printing {
+            correlated: true,
+            ..Printing::default()
+        };
+        assert_eq!(
+            total_printed(
+                num_str,
+                ExactSum::<Uncertain>::default(),
+                &parsing,
+                &printing
+            ),
+            Ok("4.00 ± 0.70\n500 ± 100 g".to_owned())
+        );
+
+        assert!(total_with("1.5±-0.1", ExactSum::<Uncertain>::default(), &parsing).is_err());
+        assert!(total("1.5±0.1", ExactSum::<Decimal>::default()).is_err());
+    }
 }
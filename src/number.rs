@@ -0,0 +1,467 @@
+use std::{fmt::Display, ops::AddAssign, str::FromStr};
+
+use crate::{
+    decimal::Decimal,
+    duration::{Duration, DurationFormat},
+    integer::{Integer, ParseIntegerError},
+    precision::Rounding,
+    rational::{ParseRationalError, Rational},
+    uncertain::Uncertain,
+};
+
+/// Unicode vulgar fractions and the fractions they stand for.
+const VULGAR_FRACTIONS: &[(char, &str)] = &[
+    ('½', "1/2"),
+    ('⅓', "1/3"),
+    ('⅔', "2/3"),
+    ('¼', "1/4"),
+    ('¾', "3/4"),
+    ('⅕', "1/5"),
+    ('⅖', "2/5"),
+    ('⅗', "3/5"),
+    ('⅘', "4/5"),
+    ('⅙', "1/6"),
+    ('⅚', "5/6"),
+    ('⅛', "1/8"),
+    ('⅜', "3/8"),
+    ('⅝', "5/8"),
+    ('⅞', "7/8"),
+];
+
+/// Numeric type that tokens can be parsed into and summed as.
+///
+/// Every `--mode` is backed by one implementation, so the tokenizer and summation are shared
+/// across all of them.
+pub trait Number: Clone + FromStr + for<'a> AddAssign<&'a Self> + Display {
+    fn zero() -> Self;
+
+    /// Exact value of `n`, or the nearest one for floating point types. `None` if the type can't
+    /// hold it exactly, as an integer can't hold `1/2`.
+    fn from_rational(n: &Rational) -> Option<Self>;
+
+    /// Formats the number in base `radix` with a `0x`, `0o` or `0b` prefix, or `None` if the type
+    /// isn't an integer type.
+    fn format_radix(&self, _radix: u32) -> Option<String> {
+        None
+    }
+
+    /// Formats the number in a duration `format`, or `None` if the type isn't a duration.
+    fn format_duration(&self, _format: DurationFormat) -> Option<String> {
+        None
+    }
+
+    /// Formats the number with its uncertainty, added up linearly if the errors summed into it
+    /// are `correlated`, and rounded as `rounded` says if set. `None` if the type has no
+    /// uncertainty.
+    fn format_uncertain(
+        &self,
+        _correlated: bool,
+        _rounded: Option<(u32, Rounding)>,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Formats the number rounded to `places` decimal places, or `None` if it isn't finite.
+    fn format_rounded(&self, places: u32, rounding: Rounding) -> Option<String> {
+        let n = self.to_rational()?;
+        Some(Decimal::round(&n, places, rounding).to_string())
+    }
+
+    /// Product with `factor`, such as an exchange rate, rounded to `scale` decimal places if the
+    /// type is exact but can't hold the product as is.
+    fn scaled_by(&self, factor: &Rational, scale: u32) -> Self;
+
+    /// Product with `factor`, such as the size of a unit, or `None` if the type is exact but
+    /// can't hold the product, as an integer can't hold half of 3.
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self>;
+
+    /// Nearest `f64`, for printing the number rounded, as `--human-readable` does.
+    fn to_f64(&self) -> f64;
+
+    /// Exact value, or for floating point types that of the shortest decimal they print as, so
+    /// an `f32` printed as `1375.9999` is rounded from that. `None` for infinities and NaN.
+    fn to_rational(&self) -> Option<Rational>;
+
+    /// Whether the number is neither an infinity nor NaN, which only floating point types have.
+    fn is_finite(&self) -> bool {
+        true
+    }
+}
+
+impl Number for f32 {
+    fn zero() -> Self {
+        0.
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        Some(n.to_f64() as f32)
+    }
+
+    fn scaled_by(&self, factor: &Rational, _: u32) -> Self {
+        (f64::from(*self) * factor.to_f64()) as f32
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Some(self.scaled_by(factor, 0))
+    }
+
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        let shortest = self
+            .is_finite()
+            .then(|| self.to_string().parse::<Decimal>())?;
+        Some(Rational::from(
+            shortest.expect("finite floats print as decimals"),
+        ))
+    }
+
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+
+impl Number for f64 {
+    fn zero() -> Self {
+        0.
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        Some(n.to_f64())
+    }
+
+    fn scaled_by(&self, factor: &Rational, _: u32) -> Self {
+        self * factor.to_f64()
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Some(self.scaled_by(factor, 0))
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        let shortest = self
+            .is_finite()
+            .then(|| self.to_string().parse::<Decimal>())?;
+        Some(Rational::from(
+            shortest.expect("finite floats print as decimals"),
+        ))
+    }
+
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+
+impl Number for Decimal {
+    fn zero() -> Self {
+        Decimal::zero()
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        Decimal::from_rational(n)
+    }
+
+    fn scaled_by(&self, factor: &Rational, scale: u32) -> Self {
+        Decimal::scaled_by(self, factor, scale)
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Decimal::from_rational(&(&Rational::from(self.clone()) * factor))
+    }
+
+    fn to_f64(&self) -> f64 {
+        Rational::from(self.clone()).to_f64()
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        Some(Rational::from(self.clone()))
+    }
+}
+
+impl Number for Integer {
+    fn zero() -> Self {
+        Integer::zero()
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        (*n.denom() == Integer::Small(1)).then(|| n.numer().clone())
+    }
+
+    fn format_radix(&self, radix: u32) -> Option<String> {
+        let prefix = match radix {
+            16 => "0x",
+            8 => "0o",
+            2 => "0b",
+            _ => "",
+        };
+        let sign = if self.is_negative() { "-" } else { "" };
+        Some(format!(
+            "{}{}{}",
+            sign,
+            prefix,
+            self.abs().to_str_radix(radix)
+        ))
+    }
+
+    fn scaled_by(&self, factor: &Rational, _: u32) -> Self {
+        (self * factor.numer()).div_round(factor.denom())
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Integer::from_rational(&(&Rational::from(self.clone()) * factor))
+    }
+
+    fn to_f64(&self) -> f64 {
+        Rational::from(self.clone()).to_f64()
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        Some(Rational::from(self.clone()))
+    }
+}
+
+impl Number for Rational {
+    fn zero() -> Self {
+        Rational::zero()
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        Some(n.clone())
+    }
+
+    fn scaled_by(&self, factor: &Rational, _: u32) -> Self {
+        self * factor
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Some(self * factor)
+    }
+
+    fn to_f64(&self) -> f64 {
+        Rational::to_f64(self)
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        Some(self.clone())
+    }
+}
+
+impl Number for Duration {
+    fn zero() -> Self {
+        Duration::zero()
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        Decimal::from_rational(n).map(Duration::from_seconds)
+    }
+
+    fn format_duration(&self, format: DurationFormat) -> Option<String> {
+        Some(self.format(format))
+    }
+
+    fn scaled_by(&self, factor: &Rational, scale: u32) -> Self {
+        Duration::from_seconds(self.seconds().scaled_by(factor, scale))
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Number::scaled_exactly(self.seconds(), factor).map(Duration::from_seconds)
+    }
+
+    fn to_f64(&self) -> f64 {
+        Number::to_f64(self.seconds())
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        Some(Rational::from(self.seconds().clone()))
+    }
+}
+
+impl Number for Uncertain {
+    fn zero() -> Self {
+        Uncertain::zero()
+    }
+
+    fn from_rational(n: &Rational) -> Option<Self> {
+        Decimal::from_rational(n).map(|value| Uncertain::new(value, Decimal::zero()))
+    }
+
+    fn format_uncertain(
+        &self,
+        correlated: bool,
+        rounded: Option<(u32, Rounding)>,
+    ) -> Option<String> {
+        Some(self.format(correlated, rounded))
+    }
+
+    fn scaled_by(&self, factor: &Rational, scale: u32) -> Self {
+        Uncertain::scaled_by(self, factor, scale)
+    }
+
+    fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        Uncertain::scaled_exactly(self, factor)
+    }
+
+    fn to_f64(&self) -> f64 {
+        Number::to_f64(self.value())
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        Some(Rational::from(self.value().clone()))
+    }
+}
+
+/// Value of `s` if it is written as a fraction, a mixed number, a percentage or an integer in
+/// another radix, such as `3/4`, `¾`, `1 1/2`, `1½`, `15%` or `0xFF`, and `None` for other
+/// numbers.
+///
+/// Percentages are read as fractions of one (`15%` is `0.15`), or as a number of percentage
+/// points (`15`) if `percent_points` is set.
+pub fn parse_exact(s: &str, percent_points: bool) -> Option<Result<Rational, ParseRationalError>> {
+    if let Some(n) = parse_radix_literal(s) {
+        return Some(n.map(Rational::from).map_err(|_| ParseRationalError));
+    }
+
+    if let Some(percent) = s.strip_suffix('%') {
+        let points = parse_exact(percent, false).unwrap_or_else(|| percent.parse());
+        let hundredth = Rational::new(Integer::Small(1), Integer::Small(100)).expect("100 isn't 0");
+        return Some(points.map(|points| {
+            if percent_points {
+                points
+            } else {
+                &points * &hundredth
+            }
+        }));
+    }
+
+    let (whole, fraction) = match s.split_once(' ') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => match s.char_indices().last() {
+            Some((i, c)) if vulgar_fraction(c).is_some() => s.split_at(i),
+            _ if s.contains('/') => return Some(s.parse()),
+            _ => return None,
+        },
+    };
+
+    // The sign of a mixed number applies to its fraction too, so `-1 1/2` is `-1.5`
+    let (negative, digits) = match whole.strip_prefix(['-', '+']) {
+        Some(digits) => (whole.starts_with('-'), digits),
+        None => (false, whole),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || fraction.contains(' ') {
+        return Some(Err(ParseRationalError));
+    }
+
+    let fraction = match fraction.parse::<char>().ok().and_then(vulgar_fraction) {
+        Some(fraction) => fraction,
+        None if fraction.starts_with(|c: char| c.is_ascii_digit()) && fraction.contains('/') => {
+            fraction
+        }
+        None => return Some(Err(ParseRationalError)),
+    };
+
+    let mut value = match digits {
+        "" => Rational::zero(),
+        digits => Rational::from(Integer::from_decimal_digits(digits.as_bytes())),
+    };
+    Some(fraction.parse().map(|fraction: Rational| {
+        value += &fraction;
+        if negative {
+            &value * &Rational::from(Integer::Small(-1))
+        } else {
+            value
+        }
+    }))
+}
+
+/// Value of `s` if it is a hexadecimal, octal or binary integer literal, such as `0xFF`,
+/// `-0o755` or `0b1010`.
+fn parse_radix_literal(s: &str) -> Option<Result<Integer, ParseIntegerError>> {
+    let unsigned = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let radix = match unsigned.get(..2)? {
+        "0x" | "0X" => 16,
+        "0o" | "0O" => 8,
+        "0b" | "0B" => 2,
+        _ => return None,
+    };
+
+    let n = Integer::from_str_radix(&unsigned[2..], radix);
+    Some(if s.starts_with('-') { n.map(|n| -n) } else { n })
+}
+
+fn vulgar_fraction(c: char) -> Option<&'static str> {
+    VULGAR_FRACTIONS
+        .iter()
+        .find(|&&(vulgar, _)| vulgar == c)
+        .map(|&(_, fraction)| fraction)
+}
+
+/// Whether `s` is the fraction of a mixed number written apart from its whole part, such as the
+/// `1/2` of `1 1/2` or the `½` of `1 ½`.
+pub fn is_mixed_fraction(s: &str) -> bool {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    match s.split_once('/') {
+        Some((num, den)) => is_digits(num) && is_digits(den),
+        None => s.parse::<char>().ok().and_then(vulgar_fraction).is_some(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fraction(s: &str) -> Option<String> {
+        parse_exact(s, false).map(|value| value.map_or("error".to_owned(), |v| v.to_string()))
+    }
+
+    #[test]
+    fn parses_fractions_mixed_numbers_and_percentages() {
+        for (s, expected) in [
+            ("3/4", Some("3/4")),
+            ("¾", Some("3/4")),
+            ("1 1/2", Some("3/2")),
+            ("-1 1/2", Some("-3/2")),
+            ("2⅓", Some("7/3")),
+            ("-½", Some("-1/2")),
+            ("15%", Some("3/20")),
+            ("12.5%", Some("1/8")),
+            ("1/2%", Some("1/200")),
+            ("1.5 1/2", Some("error")),
+            ("1 -1/2", Some("error")),
+            ("1 x", Some("error")),
+            ("1.5", None),
+            ("1e5", None),
+            ("0xFF", Some("255")),
+            ("-0o755", Some("-493")),
+            ("+0b1010", Some("10")),
+            ("0x1G", Some("error")),
+            ("0x", Some("error")),
+        ] {
+            assert_eq!(fraction(s).as_deref(), expected, "{}", s);
+        }
+
+        assert_eq!(parse_exact("15%", true), Some(Ok("15".parse().unwrap())));
+    }
+
+    #[test]
+    fn converts_fractions_exactly_or_not_at_all() {
+        let rational = |s: &str| s.parse::<Rational>().unwrap();
+
+        assert_eq!(
+            Decimal::from_rational(&rational("3/8")).map(|n| n.to_string()),
+            Some("0.375".to_owned())
+        );
+        assert_eq!(Decimal::from_rational(&rational("1/3")), None);
+        assert_eq!(
+            Integer::from_rational(&rational("6/2")),
+            Some(Integer::Small(3))
+        );
+        assert_eq!(Integer::from_rational(&rational("1/2")), None);
+        assert_eq!(f64::from_rational(&rational("1/4")), Some(0.25));
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    error::{Error, ParseErrors, Skipped},
+    summation::Merge,
+};
+
+/// Nominal size of the chunks a file is split into. It is fixed rather than derived from the
+/// thread count, so that floating point sums come out identical however many threads are used.
+const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Sums of the chunks a thread processed and the number of newlines read from them, by chunk
+/// index.
+type ChunkSums<A> = Vec<(usize, Result<(A, Skipped), Error>, usize)>;
+
+/// Part of a file that is summed on its own. It counts the newlines read from it, so the lines of
+/// the chunks after it can be numbered without reading it again.
+pub struct Chunk<'a> {
+    reader: io::Take<File>,
+    newlines: &'a Cell<usize>,
+}
+
+impl Read for Chunk<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        let newlines = buf[..n].iter().filter(|&&b| b == b'\n').count();
+        self.newlines.set(self.newlines.get() + newlines);
+        Ok(n)
+    }
+}
+
+/// Sums a file on `threads` threads by splitting it into newline aligned chunks.
+///
+/// Each chunk is summed by `sum_chunk` from a fresh accumulator, and the partial sums are merged
+/// pairwise in file order, so the result only depends on the file's contents. Skipped tokens and
+/// parse errors of all chunks are collected in file order, the latter up to their limit, with
+/// their line numbers moved from the chunk's to the file's. Any other error stops the sum, and
+/// the first one in the file is returned.
+///
+/// Inputs that aren't regular files, such as pipes, can't be split up, and are summed as a
+/// single chunk as they are read.
+pub fn sum_file<A, F>(path: &Path, threads: usize, sum_chunk: F) -> Result<(A, Skipped), Error>
+where
+    A: Merge + Send,
+    F: Fn(Chunk<'_>) -> Result<(A, Skipped), Error> + Sync,
+{
+    sum_file_in_chunks(path, threads, CHUNK_SIZE, sum_chunk)
+}
+
+fn sum_file_in_chunks<A, F>(
+    path: &Path,
+    threads: usize,
+    chunk_size: u64,
+    sum_chunk: F,
+) -> Result<(A, Skipped), Error>
+where
+    A: Merge + Send,
+    F: Fn(Chunk<'_>) -> Result<(A, Skipped), Error> + Sync,
+{
+    let io_error = |e: io::Error| Error::Io(format!("{}: {}", path.display(), e));
+    let file = File::open(path).map_err(io_error)?;
+    let metadata = file.metadata().map_err(io_error)?;
+    if !metadata.is_file() {
+        return sum_chunk(Chunk {
+            reader: file.take(u64::MAX),
+            newlines: &Cell::new(0),
+        });
+    }
+
+    let len = metadata.len();
+    let chunk_count = len.div_ceil(chunk_size).max(1) as usize;
+
+    let next_chunk = AtomicUsize::new(0);
+    let first_failed = AtomicUsize::new(usize::MAX);
+
+    let worker = || -> Result<ChunkSums<A>, Error> {
+        let mut file = File::open(path).map_err(io_error)?;
+        let mut sums = Vec::new();
+
+        loop {
+            let i = next_chunk.fetch_add(1, Ordering::Relaxed);
+            // Chunks after a failed one are skipped, but earlier ones still run so the first
+            // error in the file is the one reported
+            if i >= chunk_count || i > first_failed.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let newlines = Cell::new(0);
+            let chunk = read_chunk(&mut file, i as u64 * chunk_size, chunk_size, len)
+                .map_err(io_error)
+                .and_then(|reader| {
+                    sum_chunk(Chunk {
+                        reader,
+                        newlines: &newlines,
+                    })
+                });
+            // Parse errors below the limit don't end the sum, as later chunks may add to them
+            let failed = match &chunk {
+                Ok(_) => false,
+                Err(Error::Parse(e)) => e.reached_limit(),
+                Err(_) => true,
+            };
+            if failed {
+                first_failed.fetch_min(i, Ordering::Relaxed);
+            }
+            sums.push((i, chunk, newlines.get()));
+        }
+
+        Ok(sums)
+    };
+
+    let mut sums = thread::scope(|scope| {
+        let workers = (0..threads.clamp(1, chunk_count))
+            .map(|_| scope.spawn(worker))
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("summing thread panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    sums.sort_unstable_by_key(|&(i, ..)| i);
+
+    let mut partial_sums = Vec::with_capacity(sums.len());
+    let mut skipped = Skipped::default();
+    let mut parse_errors: Option<ParseErrors> = None;
+    // Every chunk before the last one summed ran to its end, so the newlines read from them add
+    // up to the lines before it
+    let mut lines_before = 0;
+    for (_, sum, newlines) in sums {
+        // Chunks after the one that reached the limit only ran because of the race with it
+        if parse_errors
+            .as_ref()
+            .is_some_and(ParseErrors::reached_limit)
+        {
+            break;
+        }
+
+        match sum {
+            Ok((sum, mut chunk_skipped)) => {
+                for token in &mut chunk_skipped.tokens {
+                    token.line += lines_before;
+                }
+                skipped.append(&mut chunk_skipped);
+                partial_sums.push(sum);
+            }
+            Err(Error::Parse(mut e)) => {
+                for error in &mut e.errors {
+                    error.line += lines_before;
+                }
+
+                match &mut parse_errors {
+                    Some(errors) => errors.errors.append(&mut e.errors),
+                    None => parse_errors = Some(e),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+        lines_before += newlines;
+    }
+
+    match parse_errors {
+        Some(mut e) => {
+            e.errors.truncate(e.limit);
+            Err(Error::Parse(e))
+        }
+        None => Ok((merge_pairwise(partial_sums), skipped)),
+    }
+}
+
+/// Positions `file` at the chunk starting at the first line start at or after `start` and
+/// ending at the first line start at or after `start + chunk_size`.
+fn read_chunk(
+    file: &mut File,
+    start: u64,
+    chunk_size: u64,
+    len: u64,
+) -> io::Result<io::Take<File>> {
+    let end = line_start_at_or_after(file, start + chunk_size, len)?;
+    let start = line_start_at_or_after(file, start, len)?;
+
+    file.seek(SeekFrom::Start(start))?;
+    Ok(file.try_clone()?.take(end.saturating_sub(start)))
+}
+
+fn line_start_at_or_after(file: &mut File, pos: u64, len: u64) -> io::Result<u64> {
+    if pos == 0 || pos >= len {
+        return Ok(pos.min(len));
+    }
+
+    // A line starts at `pos` if the byte before it is a newline
+    file.seek(SeekFrom::Start(pos - 1))?;
+    let mut buf = [0; 4096];
+    let mut offset = pos - 1;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(len);
+        }
+        if let Some(i) = buf[..n].iter().position(|&b| b == b'\n') {
+            return Ok(offset + i as u64 + 1);
+        }
+        offset += n as u64;
+    }
+}
+
+/// Merges neighbouring sums in rounds until one is left, keeping floating point error
+/// logarithmic in the number of chunks.
+fn merge_pairwise<A: Merge>(mut sums: Vec<A>) -> A {
+    while sums.len() > 1 {
+        let mut merged = Vec::with_capacity(sums.len().div_ceil(2));
+        let mut sums_iter = sums.into_iter();
+        while let Some(mut sum) = sums_iter.next() {
+            if let Some(other) = sums_iter.next() {
+                sum.merge(other);
+            }
+            merged.push(sum);
+        }
+        sums = merged;
+    }
+
+    sums.pop().expect("a file always has at least one chunk")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, io::Read};
+
+    use super::*;
+    use crate::{
+        error::ParseError,
+        summation::{Accumulator, Algorithm, FloatSum},
+    };
+
+    fn sum_chunk(chunk: Chunk<'_>) -> Result<(FloatSum<f32>, Skipped), Error> {
+        sum_lines(chunk, 1)
+    }
+
+    fn sum_lines(
+        mut chunk: Chunk<'_>,
+        max_errors: usize,
+    ) -> Result<(FloatSum<f32>, Skipped), Error> {
+        let mut buf = String::new();
+        chunk.read_to_string(&mut buf).unwrap();
+
+        let mut sum = FloatSum::new(Algorithm::Naive);
+        let mut errors = Vec::new();
+        for (i, line) in buf.lines().enumerate() {
+            match line.parse() {
+                Ok(n) => sum.add(&n),
+                Err(_) => errors.push(ParseError {
+                    message: format!("bad line '{}'", line),
+                    source: "test".to_owned(),
+                    line: i + 1,
+                    column: 1,
+                    token: line.to_owned(),
+                    line_text: None,
+                }),
+            }
+            if errors.len() == max_errors {
+                break;
+            }
+        }
+
+        if errors.is_empty() {
+            Ok((sum, Skipped::default()))
+        } else {
+            Err(Error::Parse(ParseErrors {
+                errors,
+                limit: max_errors,
+            }))
+        }
+    }
+
+    fn with_file(name: &str, contents: &str, test: impl FnOnce(&Path)) {
+        let path = env::temp_dir().join(format!("rsum-parallel-{}-{}", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        test(&path);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn result_does_not_depend_on_thread_count() {
+        let contents = (1..2_000).map(|i| format!("{}.1\n", i)).collect::<String>();
+
+        with_file("threads", &contents, |path| {
+            let sums = [1, 2, 3, 16].map(|threads| {
+                sum_file_in_chunks(path, threads, 64, sum_chunk)
+                    .unwrap()
+                    .0
+                    .total()
+            });
+
+            assert!(sums.iter().all(|&sum| sum == sums[0]), "{:?}", sums);
+            assert!((sums[0] - 1_999_199.9).abs() < 100.);
+        });
+    }
+
+    #[test]
+    fn chunks_split_on_line_starts() {
+        let contents = "1\n22\n333\n4444\n55555\n666666";
+
+        with_file("lines", contents, |path| {
+            for chunk_size in 1..8 {
+                let (sum, _) = sum_file_in_chunks(path, 4, chunk_size, sum_chunk).unwrap();
+                assert_eq!(sum.total(), 727_021., "chunk size {}", chunk_size);
+            }
+        });
+    }
+
+    #[test]
+    fn reports_the_first_failing_chunk_at_its_line_in_the_file() {
+        let contents = "1\n2\n3\nx\n2\n3\ny\n";
+
+        with_file("errors", contents, |path| {
+            let Err(Error::Parse(e)) = sum_file_in_chunks(path, 4, 2, sum_chunk) else {
+                panic!("expected a parse error");
+            };
+            assert_eq!(e.errors.len(), 1);
+            assert_eq!(
+                (e.errors[0].message.as_str(), e.errors[0].line),
+                ("bad line 'x'", 4)
+            );
+        });
+    }
+
+    #[test]
+    fn collects_parse_errors_of_all_chunks_up_to_the_limit() {
+        let contents = "x\n1\ny\n2\nz\n3\nw\n";
+
+        with_file("all-errors", contents, |path| {
+            for (max_errors, expected) in [(3, vec![1, 3, 5]), (10, vec![1, 3, 5, 7])] {
+                let Err(Error::Parse(e)) =
+                    sum_file_in_chunks(path, 4, 3, |chunk| sum_lines(chunk, max_errors))
+                else {
+                    panic!("expected a parse error");
+                };
+                let lines = e.errors.iter().map(|e| e.line).collect::<Vec<_>>();
+                assert_eq!(lines, expected, "limit {}", max_errors);
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn sums_inputs_that_cannot_be_split_up_as_they_are_read() {
+        use std::{io::Write, os::fd::AsRawFd};
+
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"1\n2\nx\n3\n").unwrap();
+        drop(writer);
+
+        let path = Path::new("/dev/fd").join(reader.as_raw_fd().to_string());
+        let Err(Error::Parse(e)) = sum_file_in_chunks(&path, 4, 2, |chunk| sum_lines(chunk, 2))
+        else {
+            panic!("expected a parse error");
+        };
+        assert_eq!(e.errors.len(), 1);
+        assert_eq!(e.errors[0].line, 3);
+    }
+}
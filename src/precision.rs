@@ -0,0 +1,158 @@
+use std::str::FromStr;
+
+use crate::{integer::Integer, rational::Rational};
+
+/// Most decimal places `--precision` rounds to, as many as the largest exponent decimals are
+/// read with, so rounding can't take up unbounded time and memory.
+pub const MAX_PLACES: u32 = 9_999;
+
+/// Number of decimal places `--precision` rounds the printed sum to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Places(u32),
+    /// As many as the least precise input has, the usual rule for adding up measurements.
+    Inputs,
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inputs" => Ok(Self::Inputs),
+            _ => match s.parse() {
+                Ok(places) if places <= MAX_PLACES => Ok(Self::Places(places)),
+                _ => Err(format!(
+                    "Invalid precision '{}'. Expected a number of decimal places up to {} or \
+                     inputs.",
+                    s, MAX_PLACES
+                )),
+            },
+        }
+    }
+}
+
+/// How numbers are rounded to a number of decimal places.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// To the nearest, with halves rounded to an even last digit, so `0.125` is `0.12`.
+    #[default]
+    HalfEven,
+    /// To the nearest, with halves rounded away from zero, so `0.125` is `0.13`.
+    HalfUp,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceil,
+    /// Towards zero, dropping the digits past the last place.
+    Truncate,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-even" => Ok(Self::HalfEven),
+            "half-up" => Ok(Self::HalfUp),
+            "floor" => Ok(Self::Floor),
+            "ceil" => Ok(Self::Ceil),
+            "truncate" => Ok(Self::Truncate),
+            _ => Err(format!(
+                "Unknown rounding '{}'. Expected one of: half-even, half-up, floor, ceil, truncate.",
+                s
+            )),
+        }
+    }
+}
+
+/// Number of decimal places a decimal such as `12.50` (2) or `1.5e-3` (4) is written with, or
+/// `None` for other numbers, such as fractions, whose precision isn't written.
+///
+/// Scientific notation with fewer decimal places than the exponent, as `1.5e3`, counts as
+/// having none.
+pub fn decimal_places(s: &str) -> Option<u32> {
+    let s = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+    if whole.len() + fraction.len() == 0 || !is_digits(whole) || !is_digits(fraction) {
+        return None;
+    }
+
+    let places = (fraction.len() as i64).saturating_sub(exponent);
+    Some(places.clamp(0, u32::MAX.into()) as u32)
+}
+
+/// Number of decimal places a number written with `places` of them has once multiplied by a
+/// positive `factor`, such as the size of its unit: `3.2` kg has one, and -2 in grams. Factors
+/// that aren't a power of ten count as the one below them, so feet are as precise as tenths of
+/// a metre.
+pub fn scaled_places(places: i64, factor: &Rational) -> i64 {
+    let digits = |n: &Integer| n.to_string().trim_start_matches('-').len() as i64;
+    let power = |exponent: i64| {
+        (0..exponent).fold(Integer::Small(1), |power, _| &power * &Integer::Small(10))
+    };
+    let (numer, denom) = (factor.numer(), factor.denom());
+
+    // The factor is at least the power of ten with as many digits as it has before the point,
+    // unless its leading digits are smaller than the denominator's
+    let exponent = digits(numer) - digits(denom);
+    let below = if exponent >= 0 {
+        numer < &(denom * &power(exponent))
+    } else {
+        &(numer * &power(-exponent)) < denom
+    };
+    places - exponent + i64::from(below)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_precisions_up_to_the_most_places() {
+        assert_eq!("inputs".parse(), Ok(Precision::Inputs));
+        assert_eq!("9999".parse(), Ok(Precision::Places(9_999)));
+        for s in ["10000", "4000000000", "-1", "all"] {
+            assert!(s.parse::<Precision>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn counts_decimal_places_as_written() {
+        for (s, expected) in [
+            ("12.50", Some(2)),
+            ("-3", Some(0)),
+            ("1.5e-3", Some(4)),
+            ("1.5E3", Some(0)),
+            (".5", Some(1)),
+            ("3/4", None),
+            ("0xFF", None),
+            ("1.5.2", None),
+            (".", None),
+        ] {
+            assert_eq!(decimal_places(s), expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn scales_decimal_places_by_a_factor() {
+        for (places, factor, expected) in [
+            (1, "1000", -2),
+            (0, "0.001", 3),
+            (2, "0.3048", 3),
+            (1, "1024", -2),
+            (1, "28.349523125", 0),
+            (0, "1", 0),
+            (0, "0.5", 1),
+        ] {
+            let scaled = scaled_places(places, &factor.parse().unwrap());
+            assert_eq!(scaled, expected, "{}", factor);
+        }
+    }
+}
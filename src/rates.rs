@@ -0,0 +1,418 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{
+    error::{Error, ParseError},
+    rational::Rational,
+};
+
+/// Currencies whose amounts have no minor unit, so they are converted to whole amounts.
+const NO_MINOR_UNIT: &[&str] = &["CLP", "ISK", "JPY", "KRW", "VND"];
+
+/// Exchange rates read from a local file, quoted as units of each currency per unit of a base
+/// currency, as in `USD,1.08` for a euro base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rates {
+    /// Base currency the rates are quoted against, if the file names it.
+    base: Option<String>,
+    rates: BTreeMap<String, Rational>,
+}
+
+impl Rates {
+    /// Reads rates from a CSV file with a `currency,rate` line per currency, or from a JSON
+    /// object such as `{"base": "EUR", "rates": {"USD": 1.08}}` or `{"USD": 1.08}`.
+    ///
+    /// An invalid file is a usage error pointing at its location, as it is given on the command
+    /// line rather than being the input.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let source = path.display().to_string();
+        let contents =
+            fs::read_to_string(path).map_err(|e| Error::Io(format!("{}: {}", source, e)))?;
+
+        let rates = if contents.trim_start().starts_with('{') {
+            Self::from_json(&contents, &source)
+        } else {
+            Self::from_csv(&contents, &source)
+        };
+        rates.map_err(|e| Error::Usage(e.to_string()))
+    }
+
+    fn from_csv(contents: &str, source: &str) -> Result<Self, ParseError> {
+        let mut rates = BTreeMap::new();
+        let mut first = true;
+
+        for (i, line) in contents.lines().enumerate() {
+            let error = |message: String, column: usize, token: &str| ParseError {
+                message,
+                source: source.to_owned(),
+                line: i + 1,
+                column,
+                token: token.to_owned(),
+                line_text: Some(line.to_owned()),
+            };
+
+            let fields = line.trim();
+            if fields.is_empty() || fields.starts_with('#') {
+                continue;
+            }
+            let column = line.len() - line.trim_start().len() + 1;
+            let Some((currency, rate)) = fields.split_once(',') else {
+                let message = "Expected a 'currency,rate' line".to_owned();
+                return Err(error(message, column, fields));
+            };
+
+            let rate_column = column + currency.len() + 1 + (rate.len() - rate.trim_start().len());
+            let (currency, rate) = (currency.trim(), rate.trim());
+            // The first line after comments may be a header such as `currency,rate`
+            let is_header =
+                first && !rate.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c));
+            first = false;
+            if is_header {
+                continue;
+            }
+
+            check_currency(currency).map_err(|message| error(message, column, currency))?;
+            let rate =
+                parse_rate(currency, rate).map_err(|message| error(message, rate_column, rate))?;
+            rates.insert(currency.to_owned(), rate);
+        }
+
+        Ok(Self { base: None, rates })
+    }
+
+    fn from_json(contents: &str, source: &str) -> Result<Self, ParseError> {
+        let mut parser = JsonParser {
+            text: contents,
+            pos: 0,
+        };
+        let error = |message: String, start: usize, end: usize| {
+            let before = &contents[..start];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let line_end = contents[start..]
+                .find('\n')
+                .map_or(contents.len(), |i| start + i);
+            ParseError {
+                message,
+                source: source.to_owned(),
+                line: before.matches('\n').count() + 1,
+                column: contents[line_start..start].chars().count() + 1,
+                token: contents[start..end].to_owned(),
+                line_text: Some(
+                    contents[line_start..line_end]
+                        .trim_end_matches('\r')
+                        .to_owned(),
+                ),
+            }
+        };
+
+        let value = parser
+            .value()
+            .and_then(|value| match parser.rest().trim() {
+                "" => Ok(value),
+                _ => Err(("Unexpected text after the rates".to_owned(), parser.pos)),
+            })
+            .map_err(|(message, pos)| {
+                let end = contents[pos..]
+                    .chars()
+                    .next()
+                    .map_or(pos, |c| pos + c.len_utf8());
+                error(message, pos, end)
+            })?;
+        let Json::Object(fields) = value.json else {
+            let message = "Expected an object of rates".to_owned();
+            return Err(error(message, value.start, value.end));
+        };
+
+        // Either the rates themselves, or a `rates` object with its `base` alongside
+        let nested = fields.iter().any(|(key, _)| key == "rates");
+        let mut base = None;
+        let mut rates = BTreeMap::new();
+        let mut add_rate = |currency: &str, value: &Spanned| -> Result<(), ParseError> {
+            let rate = match &value.json {
+                Json::Number(rate) => parse_rate(currency, rate),
+                _ => Err(format!("Expected a number as the rate of {}", currency)),
+            };
+            let rate = check_currency(currency)
+                .and(rate)
+                .map_err(|message| error(message, value.start, value.end))?;
+            rates.insert(currency.to_owned(), rate);
+            Ok(())
+        };
+
+        for (key, value) in &fields {
+            match (key.as_str(), &value.json) {
+                ("rates", Json::Object(nested_rates)) => {
+                    for (currency, rate) in nested_rates {
+                        add_rate(currency, rate)?;
+                    }
+                }
+                ("base", Json::String(currency)) if nested => base = Some(currency.clone()),
+                _ if nested => {}
+                (currency, _) => add_rate(currency, value)?,
+            }
+        }
+
+        Ok(Self { base, rates })
+    }
+
+    /// Whether there is a rate for `currency`.
+    pub fn covers(&self, currency: &str) -> bool {
+        self.rates.contains_key(currency) || self.base.as_deref() == Some(currency)
+    }
+
+    /// Units of the currency `to` per unit of `from`, if there are rates for both.
+    pub fn rate(&self, from: &str, to: &str) -> Option<Rational> {
+        let units_per_base = |currency: &str| match self.rates.get(currency) {
+            Some(rate) => Some(rate.clone()),
+            None if self.base.as_deref() == Some(currency) => Some(one()),
+            None => None,
+        };
+
+        if from == to {
+            return Some(one());
+        }
+        let (from, to) = (units_per_base(from)?, units_per_base(to)?);
+        Rational::new(to.numer() * from.denom(), to.denom() * from.numer())
+    }
+}
+
+/// Conversion of every amount into one currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversion {
+    /// ISO 4217 code of the currency converted to.
+    pub to: &'static str,
+    pub rates: Rates,
+}
+
+impl Conversion {
+    /// Units of the currency converted to per unit of `currency`, if there is a rate for it.
+    pub fn rate(&self, currency: &str) -> Option<Rational> {
+        self.rates.rate(currency, self.to)
+    }
+
+    /// Decimal places converted amounts are rounded to, those of the currency's minor unit.
+    pub fn scale(&self) -> u32 {
+        if NO_MINOR_UNIT.contains(&self.to) {
+            0
+        } else {
+            2
+        }
+    }
+}
+
+fn one() -> Rational {
+    "1".parse().expect("1 is a valid rational")
+}
+
+fn check_currency(currency: &str) -> Result<(), String> {
+    if currency.len() == 3 && currency.bytes().all(|b| b.is_ascii_uppercase()) {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not an ISO 4217 currency code", currency))
+    }
+}
+
+fn parse_rate(currency: &str, rate: &str) -> Result<Rational, String> {
+    match rate.parse::<Rational>() {
+        Ok(rate) if !rate.numer().is_negative() && !rate.numer().is_zero() => Ok(rate),
+        _ => Err(format!("Invalid exchange rate '{}' for {}", rate, currency)),
+    }
+}
+
+/// JSON value along with the byte offsets it starts and ends at.
+struct Spanned {
+    json: Json,
+    start: usize,
+    end: usize,
+}
+
+/// The parts of JSON that rates files are made of. Numbers are kept as written so they can be
+/// read exactly, and values that can't be rates are only checked for being well formed.
+enum Json {
+    Object(Vec<(String, Spanned)>),
+    String(String),
+    Number(String),
+    Other,
+}
+
+/// Parser for JSON, failing with a message and the offset it applies to.
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), (String, usize)> {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err((format!("Expected '{}'", c), self.pos))
+        }
+    }
+
+    fn value(&mut self) -> Result<Spanned, (String, usize)> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let json = match self.rest().chars().next() {
+            Some('{') => Json::Object(self.members('{', '}', |parser| {
+                let key = parser.string()?;
+                parser.expect(':')?;
+                Ok((key, parser.value()?))
+            })?),
+            Some('[') => {
+                self.members('[', ']', Self::value)?;
+                Json::Other
+            }
+            Some('"') => Json::String(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let len = self
+                    .rest()
+                    .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+                    .unwrap_or(self.rest().len());
+                self.pos += len;
+                Json::Number(self.text[start..self.pos].to_owned())
+            }
+            _ => {
+                let literal = ["true", "false", "null"]
+                    .into_iter()
+                    .find(|literal| self.rest().starts_with(literal))
+                    .ok_or(("Expected a JSON value".to_owned(), start))?;
+                self.pos += literal.len();
+                Json::Other
+            }
+        };
+
+        Ok(Spanned {
+            json,
+            start,
+            end: self.pos,
+        })
+    }
+
+    /// Parses the comma separated members of an object or array with `member`.
+    fn members<T>(
+        &mut self,
+        open: char,
+        close: char,
+        mut member: impl FnMut(&mut Self) -> Result<T, (String, usize)>,
+    ) -> Result<Vec<T>, (String, usize)> {
+        self.expect(open)?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.rest().starts_with(close) {
+            self.pos += 1;
+            return Ok(members);
+        }
+
+        loop {
+            members.push(member(self)?);
+            self.skip_whitespace();
+            if self.rest().starts_with(',') {
+                self.pos += 1;
+            } else {
+                self.expect(close)?;
+                return Ok(members);
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, (String, usize)> {
+        self.expect('"')?;
+        let start = self.pos;
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(string);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => string.push('\n'),
+                    Some((_, 't')) => string.push('\t'),
+                    Some((_, c @ ('"' | '\\' | '/'))) => string.push(c),
+                    _ => return Err(("Unsupported escape in string".to_owned(), start + i)),
+                },
+                c => string.push(c),
+            }
+        }
+
+        Err(("Unterminated string".to_owned(), start - 1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, process};
+
+    use super::*;
+
+    fn rate(rates: &Rates, from: &str, to: &str) -> Option<String> {
+        rates.rate(from, to).map(|rate| rate.to_string())
+    }
+
+    #[test]
+    fn reads_csv_rates() {
+        let rates =
+            Rates::from_csv("currency,rate\nEUR,1\nUSD,1.08\n\n# yen\nJPY, 160\n", "r").unwrap();
+        let commented =
+            Rates::from_csv("# from the bank\n\ncurrency,rate\nEUR,1\nUSD,1.08\n", "r").unwrap();
+        assert_eq!(rate(&commented, "EUR", "USD"), Some("27/25".to_owned()));
+        assert!(Rates::from_csv("EUR,1\ncurrency,rate\n", "r").is_err());
+
+        assert_eq!(rate(&rates, "EUR", "USD"), Some("27/25".to_owned()));
+        assert_eq!(rate(&rates, "USD", "JPY"), Some("4000/27".to_owned()));
+        assert_eq!(rate(&rates, "GBP", "USD"), None);
+        assert_eq!(rate(&rates, "GBP", "GBP"), Some("1".to_owned()));
+    }
+
+    #[test]
+    fn reads_json_rates() {
+        let json = r#"{"base": "EUR", "date": "2026-10-01", "rates": {"USD": 1.08, "GBP": 0.85}}"#;
+        let rates = Rates::from_json(json, "r").unwrap();
+        assert_eq!(rate(&rates, "USD", "EUR"), Some("25/27".to_owned()));
+        assert_eq!(rate(&rates, "GBP", "USD"), Some("108/85".to_owned()));
+
+        let rates = Rates::from_json(r#"{"USD": 1, "CHF": 0.8}"#, "r").unwrap();
+        assert_eq!(rate(&rates, "CHF", "USD"), Some("5/4".to_owned()));
+    }
+
+    #[test]
+    fn reports_the_location_of_invalid_rates() {
+        let e = Rates::from_csv("EUR,1\nUSD,-1.08\n", "rates.csv").unwrap_err();
+        assert_eq!(e.message, "Invalid exchange rate '-1.08' for USD");
+        assert_eq!((e.line, e.column, e.token.as_str()), (2, 5, "-1.08"));
+
+        let e = Rates::from_json("{\n  \"USD\": \"x\"\n}", "rates.json").unwrap_err();
+        assert_eq!(e.message, "Expected a number as the rate of USD");
+        assert_eq!((e.line, e.column, e.token.as_str()), (2, 10, "\"x\""));
+
+        let e = Rates::from_json("{\"USD\": 1,}", "rates.json").unwrap_err();
+        assert_eq!(e.message, "Expected '\"'");
+
+        let path = env::temp_dir().join(format!("rsum-bad-rates-{}.csv", process::id()));
+        fs::write(&path, "EUR,1\nUSD,x\n").unwrap();
+        let e = Rates::from_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        let Error::Usage(message) = &e else {
+            panic!("expected a usage error");
+        };
+        assert!(
+            message.contains(&format!("{}:2:5", path.display())),
+            "{}",
+            message
+        );
+        assert_eq!(e.exit_code(), 64);
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    fmt,
+    ops::{AddAssign, Mul},
+    str::FromStr,
+};
+
+use crate::{decimal::Decimal, integer::Integer};
+
+/// Exact fraction kept in lowest terms with a positive denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    num: Integer,
+    den: Integer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRationalError;
+
+impl Rational {
+    pub fn zero() -> Self {
+        Self {
+            num: Integer::zero(),
+            den: Integer::Small(1),
+        }
+    }
+
+    /// Builds `num / den` in lowest terms, or `None` if `den` is zero.
+    pub fn new(num: Integer, den: Integer) -> Option<Self> {
+        if den.is_zero() {
+            return None;
+        }
+
+        let (num, den) = if den.is_negative() {
+            (-num, -den)
+        } else {
+            (num, den)
+        };
+
+        let gcd = num.gcd(&den);
+        if gcd == Integer::Small(1) || gcd.is_zero() {
+            return Some(Self { num, den });
+        }
+
+        Some(Self {
+            num: num.div_rem(&gcd).0,
+            den: den.div_rem(&gcd).0,
+        })
+    }
+
+    pub fn numer(&self) -> &Integer {
+        &self.num
+    }
+
+    pub fn denom(&self) -> &Integer {
+        &self.den
+    }
+
+    /// Nearest `f64`, or an infinity if the fraction is out of its range.
+    pub fn to_f64(&self) -> f64 {
+        let to_f64 = |n: &Integer| n.to_string().parse::<f64>().expect("integers parse as f64");
+        to_f64(&self.num) / to_f64(&self.den)
+    }
+}
+
+impl From<Integer> for Rational {
+    fn from(n: Integer) -> Self {
+        Self {
+            num: n,
+            den: Integer::Small(1),
+        }
+    }
+}
+
+impl From<Decimal> for Rational {
+    fn from(n: Decimal) -> Self {
+        let den = Integer::Small(1).mul_pow10(n.scale());
+        Self::new(n.unscaled().clone(), den).expect("a power of ten is never zero")
+    }
+}
+
+impl FromStr for Rational {
+    type Err = ParseRationalError;
+
+    /// Parses either a decimal or a fraction `numerator/denominator` whose parts are decimals.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_decimal = |s: &str| {
+            s.parse::<Decimal>()
+                .map(Rational::from)
+                .map_err(|_| ParseRationalError)
+        };
+
+        let Some((num, den)) = s.split_once('/') else {
+            return parse_decimal(s);
+        };
+
+        let (num, den) = (parse_decimal(num)?, parse_decimal(den)?);
+        Self::new(&num.num * &den.den, &num.den * &den.num).ok_or(ParseRationalError)
+    }
+}
+
+impl AddAssign<&Rational> for Rational {
+    fn add_assign(&mut self, rhs: &Rational) {
+        let sum = if self.den == rhs.den {
+            let mut num = self.num.clone();
+            num += &rhs.num;
+            Self::new(num, self.den.clone())
+        } else {
+            let mut num = &self.num * &rhs.den;
+            num += &(&rhs.num * &self.den);
+            Self::new(num, &self.den * &rhs.den)
+        };
+
+        *self = sum.expect("denominators are never zero");
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: &Rational) -> Self::Output {
+        Rational::new(&self.num * &rhs.num, &self.den * &rhs.den)
+            .expect("denominators are never zero")
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == Integer::Small(1) {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rat(s: &str) -> Rational {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn can_parse_fractions_and_decimals_in_lowest_terms() {
+        assert_eq!(rat("2/4").to_string(), "1/2");
+        assert_eq!(rat("3/-6").to_string(), "-1/2");
+        assert_eq!(rat("0.25").to_string(), "1/4");
+        assert_eq!(rat("1.5/0.5").to_string(), "3");
+        assert_eq!(rat("0/7"), Rational::zero());
+    }
+
+    #[test]
+    fn rejects_malformed_fractions() {
+        for s in ["1/0", "1/", "/2", "1/2/3", "a/b"] {
+            assert_eq!(s.parse::<Rational>(), Err(ParseRationalError), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn sums_exactly() {
+        let mut sum = Rational::zero();
+        for n in ["1/3", "1/3", "1/3", "1/6", "-0.5"] {
+            sum += &rat(n);
+        }
+        assert_eq!(sum.to_string(), "2/3");
+    }
+
+    #[test]
+    fn multiplies_in_lowest_terms() {
+        assert_eq!((&rat("2/3") * &rat("-3/4")).to_string(), "-1/2");
+        assert_eq!((&rat("1.08") * &rat("25")).to_string(), "27");
+        assert_eq!(rat("-1/4").to_f64(), -0.25);
+    }
+}
@@ -0,0 +1,568 @@
+use std::str::FromStr;
+
+/// Largest count of a `{m,n}` quantifier, as every repetition is compiled separately.
+const MAX_REPEAT: usize = 1000;
+
+/// Largest number of instructions a regex compiles to, which nested quantifiers multiply.
+const MAX_PROGRAM_LEN: usize = 100_000;
+
+/// Small regular expression engine, enough for splitting lines on a delimiter.
+///
+/// Supports literals, `.`, character classes such as `[^a-z\d]`, the `\d`, `\s` and `\w` escapes
+/// and their negations, groups, alternation, the `*`, `+`, `?` and `{m,n}` quantifiers, with
+/// counts up to 1000, and the `^` and `$` anchors. Matching is greedy and leftmost, like Perl's.
+///
+/// Regexes are compiled to a program that runs on all positions a match could have reached at
+/// once (a Pike VM), so matching takes time linear in the text and doesn't recurse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    program: Vec<Inst>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    /// Any character except a newline.
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClassItem {
+    Range(char, char),
+    Digit,
+    Space,
+    Word,
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            Self::Range(start, end) => (start..=end).contains(&c),
+            Self::Digit => c.is_ascii_digit(),
+            Self::Space => c.is_whitespace(),
+            Self::Word => c.is_alphanumeric() || c == '_',
+        }
+    }
+}
+
+/// Instruction of a compiled regex, which jumps to the next one unless it says otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Jump(usize),
+    /// Continues at both instructions, preferring the first.
+    Split(usize, usize),
+    Match,
+}
+
+impl Inst {
+    /// Whether the instruction consumes `c`, if it is one that consumes a character.
+    fn consumes(&self, c: Option<char>) -> bool {
+        match self {
+            Self::Char(expected) => c == Some(*expected),
+            Self::Any => c.is_some_and(|c| c != '\n'),
+            Self::Class { items, negated } => {
+                c.is_some_and(|c| items.iter().any(|item| item.matches(c)) != *negated)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Regex {
+    /// Start and end, in characters, of the leftmost match in `text` starting at or after
+    /// `start`.
+    pub fn find_at(&self, text: &[char], start: usize) -> Option<(usize, usize)> {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut matched = None;
+
+        for pos in start..=text.len() {
+            // A match starting here is only wanted if none started further left
+            if matched.is_none() {
+                self.add_thread(&mut current, 0, pos, text, pos);
+            }
+            if matched.is_some() && current.threads.is_empty() {
+                break;
+            }
+
+            let c = text.get(pos).copied();
+            for &(pc, match_start) in &current.threads {
+                if self.program[pc] == Inst::Match {
+                    // Threads after this one are less preferred, so they are dropped
+                    matched = Some((match_start, pos));
+                    break;
+                }
+                if self.program[pc].consumes(c) {
+                    self.add_thread(&mut next, pc + 1, match_start, text, pos + 1);
+                }
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+
+        matched
+    }
+
+    /// Adds the thread at `pc` to `threads`, following jumps and anchors up to the instructions
+    /// that consume a character or match, in order of preference.
+    fn add_thread(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        match_start: usize,
+        text: &[char],
+        pos: usize,
+    ) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            // A thread already at `pc` was preferred, and would go the same way from here
+            if !threads.visit(pc) {
+                continue;
+            }
+
+            match self.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Inst::Start if pos == 0 => stack.push(pc + 1),
+                Inst::End if pos == text.len() => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => threads.threads.push((pc, match_start)),
+            }
+        }
+    }
+}
+
+/// Instructions that threads are at, with the position their match started at, in order of
+/// preference.
+struct Threads {
+    threads: Vec<(usize, usize)>,
+    visited: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Self {
+            threads: Vec::new(),
+            visited: vec![false; len],
+        }
+    }
+
+    /// Marks `pc` as visited, returning whether it wasn't already.
+    fn visit(&mut self, pc: usize) -> bool {
+        !std::mem::replace(&mut self.visited[pc], true)
+    }
+
+    fn clear(&mut self) {
+        self.threads.clear();
+        self.visited.fill(false);
+    }
+}
+
+/// Number of instructions [`compile`] appends for `node`, saturating for huge regexes.
+fn program_len(node: &Node) -> usize {
+    match node {
+        Node::Char(_) | Node::Any | Node::Class { .. } | Node::Start | Node::End => 1,
+        Node::Concat(nodes) => nodes.iter().map(program_len).fold(0, usize::saturating_add),
+        Node::Alternation(nodes) => nodes
+            .iter()
+            .map(|node| program_len(node).saturating_add(2))
+            .fold(0, usize::saturating_add),
+        Node::Repeat { node, min, max } => {
+            let len = program_len(node);
+            let optional = match max {
+                None => len.saturating_add(2),
+                Some(max) => (max - min).saturating_mul(len.saturating_add(1)),
+            };
+            min.saturating_mul(len).saturating_add(optional)
+        }
+    }
+}
+
+/// Appends the instructions matching `node` to `program`.
+fn compile(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Char(c) => program.push(Inst::Char(*c)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class { items, negated } => program.push(Inst::Class {
+            items: items.clone(),
+            negated: *negated,
+        }),
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program);
+            }
+        }
+        Node::Alternation(nodes) => {
+            let mut jumps = Vec::new();
+            for (i, node) in nodes.iter().enumerate() {
+                if i + 1 == nodes.len() {
+                    compile(node, program);
+                    break;
+                }
+
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(node, program);
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+
+            for jump in jumps {
+                program[jump] = Inst::Jump(program.len());
+            }
+        }
+        Node::Repeat { node, min, max } => {
+            for _ in 0..*min {
+                compile(node, program);
+            }
+
+            match max {
+                // Repetitions past the minimum that don't consume anything get back to the
+                // split they started at, where they end, so empty matches can't loop
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(node, program);
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let splits = (*min..*max)
+                        .map(|_| {
+                            let split = program.len();
+                            program.push(Inst::Split(split + 1, 0));
+                            compile(node, program);
+                            split
+                        })
+                        .collect::<Vec<_>>();
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, program.len());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Regex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+        };
+        let node = parser.alternation()?;
+
+        match parser.peek() {
+            None if program_len(&node) > MAX_PROGRAM_LEN => {
+                Err(format!("Regex '{}' is too large.", s))
+            }
+            None => {
+                let mut program = Vec::new();
+                compile(&node, &mut program);
+                program.push(Inst::Match);
+                Ok(Self { program })
+            }
+            Some(c) => Err(format!("Unexpected '{}' in regex '{}'.", c, s)),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let matched = self.peek() == Some(c);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+
+        Ok(match branches.len() {
+            1 => branches.pop().unwrap(),
+            _ => Node::Alternation(branches),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn quantified(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => return self.counted(node),
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+        })
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}` after `node`.
+    fn counted(&mut self, node: Node) -> Result<Node, String> {
+        self.pos += 1;
+        let min = self
+            .number()
+            .ok_or("Expected a count after '{' in regex.")?;
+        let max = if self.eat(',') {
+            self.number()
+        } else {
+            Some(min)
+        };
+        if !self.eat('}') || max.is_some_and(|max| max < min) || max.unwrap_or(min) > MAX_REPEAT {
+            return Err("Invalid repetition count in regex.".to_owned());
+        }
+
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+        })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some('(') => {
+                // Groups never capture, so `(?:` is only accepted for familiarity
+                if self.eat('?') && !self.eat(':') {
+                    return Err("Unsupported group in regex.".to_owned());
+                }
+                let node = self.alternation()?;
+                if !self.eat(')') {
+                    return Err("Unclosed '(' in regex.".to_owned());
+                }
+                Ok(node)
+            }
+            Some('[') => self.class(),
+            Some('.') => Ok(Node::Any),
+            Some('^') => Ok(Node::Start),
+            Some('$') => Ok(Node::End),
+            Some('\\') => self.escape(),
+            Some(c @ ('*' | '+' | '?' | '{')) => {
+                Err(format!("Nothing to repeat before '{}' in regex.", c))
+            }
+            Some(c) => Ok(Node::Char(c)),
+            None => Err("Unexpected end of regex.".to_owned()),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let class = |item, negated| Node::Class {
+            items: vec![item],
+            negated,
+        };
+
+        Ok(match self.next() {
+            Some('d') => class(ClassItem::Digit, false),
+            Some('D') => class(ClassItem::Digit, true),
+            Some('s') => class(ClassItem::Space, false),
+            Some('S') => class(ClassItem::Space, true),
+            Some('w') => class(ClassItem::Word, false),
+            Some('W') => class(ClassItem::Word, true),
+            Some(c) => Node::Char(escaped_char(c)?),
+            None => return Err("Unexpected end of regex after '\\'.".to_owned()),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+
+        // A `]` right at the start is a literal
+        let mut first = true;
+        loop {
+            let c = match self.next() {
+                Some(']') if !first => break,
+                Some(c) => c,
+                None => return Err("Unclosed '[' in regex.".to_owned()),
+            };
+            first = false;
+
+            let start = match c {
+                '\\' => match self.next() {
+                    Some('d') => {
+                        items.push(ClassItem::Digit);
+                        continue;
+                    }
+                    Some('s') => {
+                        items.push(ClassItem::Space);
+                        continue;
+                    }
+                    Some('w') => {
+                        items.push(ClassItem::Word);
+                        continue;
+                    }
+                    Some(c) => escaped_char(c)?,
+                    None => return Err("Unclosed '[' in regex.".to_owned()),
+                },
+                c => c,
+            };
+
+            let is_range =
+                self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']');
+            if is_range {
+                self.pos += 1;
+                let end = match self.next() {
+                    Some('\\') => escaped_char(self.next().unwrap_or('\\'))?,
+                    Some(c) => c,
+                    None => return Err("Unclosed '[' in regex.".to_owned()),
+                };
+                if end < start {
+                    return Err(format!("Invalid range '{}-{}' in regex.", start, end));
+                }
+                items.push(ClassItem::Range(start, end));
+            } else {
+                items.push(ClassItem::Range(start, start));
+            }
+        }
+
+        Ok(Node::Class { items, negated })
+    }
+}
+
+/// Character that `\c` stands for, outside of the class escapes.
+fn escaped_char(c: char) -> Result<char, String> {
+    match c {
+        't' => Ok('\t'),
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        c if c.is_ascii_alphanumeric() => Err(format!("Unknown escape '\\{}' in regex.", c)),
+        c => Ok(c),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn find(regex: &str, text: &str) -> Option<(usize, usize)> {
+        let regex = regex.parse::<Regex>().unwrap();
+        regex.find_at(&text.chars().collect::<Vec<_>>(), 0)
+    }
+
+    #[test]
+    fn finds_leftmost_longest_match() {
+        assert_eq!(find(r"\s*[,;]\s*", "1 ,  2"), Some((1, 5)));
+        assert_eq!(find("a|ab", "xab"), Some((1, 2)));
+        assert_eq!(find("(ab)+", "abababc"), Some((0, 6)));
+        assert_eq!(find(r"\d{2,3}", "1 2345"), Some((2, 5)));
+        assert_eq!(find("[^0-9.]+", "1.5abc2"), Some((3, 6)));
+        assert_eq!(find("x?", "abc"), Some((0, 0)));
+        assert_eq!(find("^b", "ab"), None);
+        assert_eq!(find("$", "ab"), Some((2, 2)));
+    }
+
+    #[test]
+    fn backtracks_into_repetitions() {
+        assert_eq!(find("a*ab", "aaab"), Some((0, 4)));
+        assert_eq!(find("(a|b)*b", "abab c"), Some((0, 4)));
+        assert_eq!(find("(x*)*y", "xxy"), Some((0, 3)));
+    }
+
+    #[test]
+    fn matches_in_linear_time() {
+        let spaces = " ".repeat(50_000);
+        assert_eq!(find(r"\s*[,;]\s*", &spaces), None);
+        assert_eq!(
+            find(r"\s*[,;]\s*", &format!("1{},2", spaces)),
+            Some((1, 50_002))
+        );
+
+        let text = "a".repeat(26);
+        let start = std::time::Instant::now();
+        assert_eq!(find("(a*)*b", &text), None);
+        assert!(start.elapsed().as_secs() < 1);
+    }
+
+    #[test]
+    fn rejects_invalid_regexes() {
+        for regex in [
+            "(a",
+            "a)",
+            "[a",
+            "*a",
+            r"\q",
+            "a{3,1}",
+            "[z-a]",
+            "a{100000000}",
+            "a{1001,}",
+            "((a{1000}){1000}){1000}",
+        ] {
+            assert!(regex.parse::<Regex>().is_err(), "{}", regex);
+        }
+    }
+}
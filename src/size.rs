@@ -0,0 +1,142 @@
+use crate::integer::Integer;
+
+/// Size suffixes and the power of 1000 or 1024 each stands for. Single letters are read as
+/// powers of 1024 the way `du -h` and `ls -lh` print them, and the `B` ending of `kB`, `MB`, ...
+/// as powers of 1000 the way `docker` does. A lone `k` isn't one, as it is a thousand elsewhere.
+const SUFFIXES: &[(&str, u32, u32)] = &[
+    ("B", 1000, 0),
+    ("K", 1024, 1),
+    ("M", 1024, 2),
+    ("G", 1024, 3),
+    ("T", 1024, 4),
+    ("P", 1024, 5),
+    ("E", 1024, 6),
+    ("kB", 1000, 1),
+    ("KB", 1000, 1),
+    ("MB", 1000, 2),
+    ("GB", 1000, 3),
+    ("TB", 1000, 4),
+    ("PB", 1000, 5),
+    ("EB", 1000, 6),
+    ("Ki", 1024, 1),
+    ("Mi", 1024, 2),
+    ("Gi", 1024, 3),
+    ("Ti", 1024, 4),
+    ("Pi", 1024, 5),
+    ("Ei", 1024, 6),
+    ("KiB", 1024, 1),
+    ("MiB", 1024, 2),
+    ("GiB", 1024, 3),
+    ("TiB", 1024, 4),
+    ("PiB", 1024, 5),
+    ("EiB", 1024, 6),
+];
+
+/// Suffixes sizes are printed with, from kilo up.
+const IEC_PREFIXES: &[&str] = &["K", "M", "G", "T", "P", "E"];
+const SI_PREFIXES: &[&str] = &["kB", "MB", "GB", "TB", "PB", "EB"];
+
+/// How `--human-readable` and `--si` print sizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeStyle {
+    /// Powers of 1024, as in `1.5K` or `200M`.
+    Iec,
+    /// Powers of 1000, as in `1.5kB` or `200MB`.
+    Si,
+}
+
+/// Splits a size suffix off a number such as `1.5K`, `200MiB` or `512 B`, returning the number
+/// and the number of bytes the suffix stands for.
+pub fn split_size(num_str: &str) -> Option<(&str, Integer)> {
+    let number = num_str.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &num_str[number.len()..];
+    let &(_, base, exp) = SUFFIXES.iter().find(|&&(s, _, _)| s == suffix)?;
+
+    let number = number.strip_suffix(' ').unwrap_or(number);
+    // Letters mean the suffix is part of something else, as the `B` of `0x1B` or the `M` of
+    // `PT30M` are
+    if !number.ends_with(|c: char| c.is_ascii_digit() || c == '.')
+        || number.contains(|c: char| c.is_alphabetic())
+    {
+        return None;
+    }
+
+    let mut bytes = Integer::Small(1);
+    for _ in 0..exp {
+        bytes = &bytes * &Integer::Small(base.into());
+    }
+    Some((number, bytes))
+}
+
+/// Whether `word` is a size suffix, such as the `B` of `512 B` written apart from its number.
+pub fn is_size_suffix(word: &str) -> bool {
+    SUFFIXES.iter().any(|&(suffix, _, _)| suffix == word)
+}
+
+/// Formats a number of bytes with the largest suffix it reaches, with one decimal place below
+/// 10 as `du -h` does, such as `1.5K` or `200M`. `None` below a kilobyte, which is printed as is.
+pub fn format_size(bytes: f64, style: SizeStyle) -> Option<String> {
+    let (base, prefixes) = match style {
+        SizeStyle::Iec => (1024., IEC_PREFIXES),
+        SizeStyle::Si => (1000., SI_PREFIXES),
+    };
+
+    let mut scaled = bytes.abs();
+    let mut suffix = None;
+    for prefix in prefixes {
+        // Moves on at 1023.5 already, which would round to 1024 of the smaller unit
+        if scaled < base - 0.5 {
+            break;
+        }
+        scaled /= base;
+        suffix = Some(prefix);
+    }
+
+    let sign = if bytes < 0. { "-" } else { "" };
+    let places = if scaled < 9.95 { 1 } else { 0 };
+    suffix.map(|suffix| format!("{}{:.*}{}", sign, places, scaled, suffix))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn splits_size_suffixes_off_numbers() {
+        for (num_str, expected) in [
+            ("1.5K", Some(("1.5", 1024))),
+            ("200MiB", Some(("200", 1024 * 1024))),
+            ("3G", Some(("3", 1024 * 1024 * 1024))),
+            ("12.3kB", Some(("12.3", 1000))),
+            ("1GB", Some(("1", 1_000_000_000))),
+            ("15Gi", Some(("15", 1024 * 1024 * 1024))),
+            ("512B", Some(("512", 1))),
+            ("512 B", Some(("512", 1))),
+            ("-2M", Some(("-2", 1024 * 1024))),
+            ("0x1B", None),
+            ("PT30M", None),
+            ("1e5", None),
+            ("K", None),
+            ("2k", None),
+            ("12 KG", None),
+        ] {
+            let split = split_size(num_str).map(|(n, bytes)| (n, bytes.to_string()));
+            let expected = expected.map(|(n, bytes)| (n, bytes.to_string()));
+            assert_eq!(split, expected, "{}", num_str);
+        }
+    }
+
+    #[test]
+    fn formats_sizes_with_the_largest_suffix() {
+        for (bytes, style, expected) in [
+            (1536., SizeStyle::Iec, Some("1.5K")),
+            (209_715_200., SizeStyle::Iec, Some("200M")),
+            (1_048_500., SizeStyle::Iec, Some("1.0M")),
+            (-3e9, SizeStyle::Si, Some("-3.0GB")),
+            (12_300., SizeStyle::Si, Some("12kB")),
+            (512., SizeStyle::Iec, None),
+        ] {
+            assert_eq!(format_size(bytes, style).as_deref(), expected, "{}", bytes);
+        }
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    ops::{Add, Div, Mul, Sub},
+    str::FromStr,
+};
+
+use crate::number::Number;
+
+/// Partial result that can be combined with another one computed separately.
+pub trait Merge {
+    /// Adds the numbers summed by `other` to this total.
+    ///
+    /// Merging is deterministic, so partial sums merged in the same order always give the same
+    /// result.
+    fn merge(&mut self, other: Self);
+}
+
+/// Running total that numbers are added to one at a time.
+pub trait Accumulator: Merge {
+    type Value: Number;
+
+    fn add(&mut self, n: &Self::Value);
+
+    fn total(&self) -> Self::Value;
+
+    /// Estimated bound on the absolute rounding error of [`Accumulator::total`], if the sum is
+    /// inexact.
+    fn error_bound(&self) -> Option<Self::Value> {
+        None
+    }
+}
+
+/// Plain running total for numeric types whose addition is exact.
+#[derive(Debug, Clone)]
+pub struct ExactSum<N>(N);
+
+impl<N: Number> Default for ExactSum<N> {
+    fn default() -> Self {
+        Self(N::zero())
+    }
+}
+
+impl<N: Number> Accumulator for ExactSum<N> {
+    type Value = N;
+
+    fn add(&mut self, n: &N) {
+        self.0 += n;
+    }
+
+    fn total(&self) -> N {
+        self.0.clone()
+    }
+}
+
+impl<N: Number> Merge for ExactSum<N> {
+    fn merge(&mut self, other: Self) {
+        self.0 += &other.0;
+    }
+}
+
+/// Floating point summation algorithm.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Left to right addition, with an error that grows linearly with the number of inputs.
+    #[default]
+    Naive,
+    /// Kahan's compensated summation, which carries the rounding error of each addition forward.
+    Kahan,
+    /// Neumaier's variant of Kahan summation, which stays accurate when an input is larger in
+    /// magnitude than the running total.
+    Neumaier,
+    /// Pairwise summation, whose error grows with the logarithm of the number of inputs.
+    Pairwise,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "naive" => Ok(Self::Naive),
+            "kahan" => Ok(Self::Kahan),
+            "neumaier" => Ok(Self::Neumaier),
+            "pairwise" => Ok(Self::Pairwise),
+            _ => Err(format!(
+                "Unknown algorithm '{}'. Expected one of: naive, kahan, neumaier, pairwise.",
+                s
+            )),
+        }
+    }
+}
+
+/// Floating point type that [`FloatSum`] can accumulate.
+pub trait Float:
+    Number
+    + Copy
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    const EPSILON: Self;
+
+    fn abs(self) -> Self;
+
+    fn from_usize(n: usize) -> Self;
+}
+
+impl Float for f32 {
+    const EPSILON: Self = f32::EPSILON;
+
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+
+    fn from_usize(n: usize) -> Self {
+        n as f32
+    }
+}
+
+impl Float for f64 {
+    const EPSILON: Self = f64::EPSILON;
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn from_usize(n: usize) -> Self {
+        n as f64
+    }
+}
+
+/// Floating point running total using one of the summation [`Algorithm`]s.
+///
+/// Alongside the total it tracks the number of inputs and the sum of their magnitudes, which
+/// the textbook error bounds for each algorithm are expressed in (Higham, *Accuracy and
+/// Stability of Numerical Algorithms*, ch. 4).
+#[derive(Debug, Clone)]
+pub struct FloatSum<F> {
+    algorithm: Algorithm,
+    sum: F,
+    compensation: F,
+    /// Partial sums of blocks of `2^level` inputs, with strictly decreasing levels.
+    pairwise: Vec<(u32, F)>,
+    count: usize,
+    abs_sum: F,
+    /// Sum of the magnitudes of the running totals, which bounds the compensation Neumaier
+    /// summation accumulates.
+    partial_abs_sum: F,
+}
+
+impl<F: Float> FloatSum<F> {
+    pub fn new(algorithm: Algorithm) -> Self {
+        Self {
+            algorithm,
+            sum: F::zero(),
+            compensation: F::zero(),
+            pairwise: Vec::new(),
+            count: 0,
+            abs_sum: F::zero(),
+            partial_abs_sum: F::zero(),
+        }
+    }
+
+    fn add_kahan(&mut self, n: F) {
+        let y = n - self.compensation;
+        let t = self.sum + y;
+        self.compensation = (t - self.sum) - y;
+        self.sum = t;
+    }
+
+    fn add_neumaier(&mut self, n: F) {
+        let t = self.sum + n;
+        if self.sum.abs() >= n.abs() {
+            self.compensation += &((self.sum - t) + n);
+        } else {
+            self.compensation += &((n - t) + self.sum);
+        }
+        self.sum = t;
+        self.partial_abs_sum += &t.abs();
+    }
+
+    /// Most additions any input went through in the pairwise partial sums, including the
+    /// final fold of the partial sums into the total.
+    fn pairwise_depth(&self) -> usize {
+        let top_level = self.pairwise.iter().map(|&(level, _)| level).max();
+        top_level.map_or(0, |level| level as usize + self.pairwise.len() - 1)
+    }
+}
+
+impl<F: Float> Accumulator for FloatSum<F> {
+    type Value = F;
+
+    fn add(&mut self, &n: &F) {
+        self.count += 1;
+        self.abs_sum += &n.abs();
+
+        match self.algorithm {
+            Algorithm::Naive => self.sum += &n,
+            Algorithm::Kahan => self.add_kahan(n),
+            Algorithm::Neumaier => self.add_neumaier(n),
+            Algorithm::Pairwise => {
+                let mut block = (0, n);
+                while let Some(&(level, partial)) = self.pairwise.last() {
+                    if level != block.0 {
+                        break;
+                    }
+                    self.pairwise.pop();
+                    block = (level + 1, partial + block.1);
+                }
+                self.pairwise.push(block);
+            }
+        }
+    }
+
+    fn total(&self) -> F {
+        match self.algorithm {
+            Algorithm::Naive | Algorithm::Kahan => self.sum,
+            Algorithm::Neumaier => self.sum + self.compensation,
+            Algorithm::Pairwise => self
+                .pairwise
+                .iter()
+                .rev()
+                .fold(F::zero(), |acc, &(_, partial)| acc + partial),
+        }
+    }
+
+    fn error_bound(&self) -> Option<F> {
+        // Unit roundoff, and gamma(k) = k * u / (1 - k * u) bounding k consecutive roundings
+        let u = F::EPSILON / F::from_usize(2);
+        let gamma = |k: usize| {
+            let ku = F::from_usize(k) * u;
+            ku / (F::from_usize(1) - ku)
+        };
+
+        let bound = match self.algorithm {
+            Algorithm::Naive => gamma(self.count.saturating_sub(1)) * self.abs_sum,
+            Algorithm::Kahan => {
+                (F::from_usize(2) * u + F::from_usize(self.count) * u * u) * self.abs_sum
+            }
+            // Each addition's error is captured exactly, but the compensation is itself summed
+            // naively, on top of the final rounding of `sum + compensation`
+            Algorithm::Neumaier => {
+                u * self.total().abs() + gamma(self.count) * u * self.partial_abs_sum
+            }
+            Algorithm::Pairwise => gamma(self.pairwise_depth()) * self.abs_sum,
+        };
+
+        Some(bound)
+    }
+}
+
+impl<F: Float> Merge for FloatSum<F> {
+    fn merge(&mut self, other: Self) {
+        self.count += other.count;
+        self.abs_sum += &other.abs_sum;
+        self.partial_abs_sum += &other.partial_abs_sum;
+
+        // The other sum's compensation carries over, and its total is added like any other input
+        match self.algorithm {
+            Algorithm::Naive => self.sum += &other.sum,
+            Algorithm::Kahan => {
+                self.compensation += &other.compensation;
+                self.add_kahan(other.sum);
+            }
+            Algorithm::Neumaier => {
+                self.compensation += &other.compensation;
+                self.add_neumaier(other.sum);
+            }
+            Algorithm::Pairwise => {
+                let depth = self.pairwise_depth().max(other.pairwise_depth());
+                let total = self.total() + other.total();
+                self.pairwise = vec![(depth as u32 + 1, total)];
+            }
+        }
+    }
+}
+
+/// Sums kept apart by currency or unit, so that amounts in different currencies or of different
+/// dimensions are never added up.
+#[derive(Debug, Clone)]
+pub struct Totals<A> {
+    /// Sum each currency starts out from.
+    zero: A,
+    /// Sums by ISO 4217 currency code or base unit symbol, with `None` for numbers without
+    /// either.
+    sums: BTreeMap<Option<&'static str>, A>,
+    /// Fewest decimal places a number added to each sum was written with, for the sums of
+    /// numbers whose precision is known. Amounts with a unit count them in the base unit, where
+    /// they are negative for amounts such as `3.2kg` that are written to fewer than 1 g.
+    places: BTreeMap<Option<&'static str>, i64>,
+}
+
+impl<A: Accumulator + Clone> Totals<A> {
+    pub fn new(zero: A) -> Self {
+        Self {
+            zero,
+            sums: BTreeMap::new(),
+            places: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, currency: Option<&'static str>, n: &A::Value) {
+        self.sums
+            .entry(currency)
+            .or_insert_with(|| self.zero.clone())
+            .add(n);
+    }
+
+    /// Notes that a number written with `places` decimal places was added to the sum of
+    /// `currency`.
+    pub fn add_places(&mut self, currency: Option<&'static str>, places: i64) {
+        self.places
+            .entry(currency)
+            .and_modify(|least| *least = places.min(*least))
+            .or_insert(places);
+    }
+
+    /// Fewest decimal places a number added to the sum of `currency` was written with, if any
+    /// of them was written with a known number.
+    pub fn least_places(&self, currency: Option<&'static str>) -> Option<i64> {
+        self.places.get(&currency).copied()
+    }
+
+    /// Whether nothing was summed.
+    pub fn is_empty(&self) -> bool {
+        self.sums.is_empty()
+    }
+
+    /// Sum of each currency, starting with the one of numbers without a currency, which is the
+    /// only one and zero if nothing was summed.
+    pub fn iter(&self) -> impl Iterator<Item = (Option<&'static str>, &A)> {
+        let nothing_summed = self.sums.is_empty().then_some((None, &self.zero));
+        nothing_summed
+            .into_iter()
+            .chain(self.sums.iter().map(|(&currency, sum)| (currency, sum)))
+    }
+}
+
+impl<A: Accumulator> Merge for Totals<A> {
+    fn merge(&mut self, other: Self) {
+        for (currency, sum) in other.sums {
+            match self.sums.entry(currency) {
+                Entry::Occupied(entry) => entry.into_mut().merge(sum),
+                Entry::Vacant(entry) => {
+                    entry.insert(sum);
+                }
+            }
+        }
+        for (currency, places) in other.places {
+            let least = self.places.entry(currency).or_insert(places);
+            *least = places.min(*least);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sum_with(algorithm: Algorithm, nums: &[f32]) -> FloatSum<f32> {
+        let mut sum = FloatSum::new(algorithm);
+        for n in nums {
+            sum.add(n);
+        }
+        sum
+    }
+
+    #[test]
+    fn compensated_and_pairwise_sums_do_not_drift() {
+        let nums = [0.1f32; 100_000];
+        let exact = 10_000.0f64;
+
+        let naive = sum_with(Algorithm::Naive, &nums).total();
+        assert!((f64::from(naive) - exact).abs() > 1.);
+
+        for algorithm in [Algorithm::Kahan, Algorithm::Neumaier, Algorithm::Pairwise] {
+            let total = sum_with(algorithm, &nums).total();
+            assert!(
+                (f64::from(total) - exact).abs() < 0.01,
+                "{:?}: {}",
+                algorithm,
+                total
+            );
+        }
+    }
+
+    #[test]
+    fn neumaier_handles_inputs_larger_than_the_total() {
+        let nums = [1., 1e8, 1., -1e8];
+
+        assert_eq!(sum_with(Algorithm::Kahan, &nums).total(), 0.);
+        assert_eq!(sum_with(Algorithm::Neumaier, &nums).total(), 2.);
+    }
+
+    #[test]
+    fn merging_matches_sequential_sum_closely() {
+        let nums = [0.1f32; 100_000];
+
+        for algorithm in [
+            Algorithm::Naive,
+            Algorithm::Kahan,
+            Algorithm::Neumaier,
+            Algorithm::Pairwise,
+        ] {
+            let sequential = sum_with(algorithm, &nums);
+            let mut merged = sum_with(algorithm, &nums[..30_000]);
+            merged.merge(sum_with(algorithm, &nums[30_000..]));
+
+            assert_eq!(merged.count, sequential.count);
+            let error = (f64::from(merged.total()) - 10_000.).abs();
+            let bound = f64::from(merged.error_bound().unwrap());
+            assert!(error <= bound, "{:?}: {} > {}", algorithm, error, bound);
+        }
+    }
+
+    #[test]
+    fn totals_keep_currencies_apart() {
+        let mut totals = Totals::new(ExactSum::<f64>::default());
+        totals.add(Some("USD"), &5.);
+        totals.add(None, &1.);
+
+        let mut other = Totals::new(ExactSum::default());
+        other.add(Some("EUR"), &3.);
+        other.add(Some("USD"), &2.);
+        totals.add_places(Some("USD"), 2);
+        other.add_places(Some("USD"), 1);
+        other.add_places(Some("EUR"), 3);
+        other.add_places(Some("EUR"), -2);
+        totals.merge(other);
+
+        assert_eq!(totals.least_places(Some("USD")), Some(1));
+        assert_eq!(totals.least_places(Some("EUR")), Some(-2));
+        assert_eq!(totals.least_places(None), None);
+
+        let sums = totals
+            .iter()
+            .map(|(currency, sum)| (currency, sum.total()))
+            .collect::<Vec<_>>();
+        assert_eq!(sums, [(None, 1.), (Some("EUR"), 3.), (Some("USD"), 7.)]);
+
+        let empty = Totals::new(ExactSum::<f64>::default());
+        assert_eq!(
+            empty.iter().map(|(_, sum)| sum.total()).collect::<Vec<_>>(),
+            [0.]
+        );
+    }
+
+    #[test]
+    fn error_bound_covers_the_actual_error() {
+        let nums = [0.1f32; 100_000];
+        let exact = f64::from(0.1f32) * 100_000.;
+
+        for algorithm in [
+            Algorithm::Naive,
+            Algorithm::Kahan,
+            Algorithm::Neumaier,
+            Algorithm::Pairwise,
+        ] {
+            let sum = sum_with(algorithm, &nums);
+            let error = (f64::from(sum.total()) - exact).abs();
+            let bound = f64::from(sum.error_bound().unwrap());
+            assert!(error <= bound, "{:?}: {} > {}", algorithm, error, bound);
+        }
+    }
+}
@@ -0,0 +1,689 @@
+use std::{
+    collections::VecDeque,
+    io::{self, ErrorKind, Read},
+    str,
+};
+
+use crate::regex::Regex;
+
+const BUF_SIZE: usize = 64 * 1024;
+
+/// Lines longer than this aren't kept around for error messages.
+const MAX_CONTEXT: usize = 4096;
+
+/// Whitespace separated word of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    /// 1-based line the token starts on.
+    pub line: usize,
+    /// 1-based column, in characters, the token starts at.
+    pub column: usize,
+}
+
+/// What separates tokens on a line.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Runs of Unicode whitespace.
+    #[default]
+    Whitespace,
+    /// A fixed string, such as `,`.
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Delimiter {
+    /// Start and end of the first non-empty delimiter in `line` at or after `start`.
+    fn find(&self, line: &[char], start: usize) -> Option<(usize, usize)> {
+        match self {
+            Self::Whitespace => unreachable!("whitespace is split on while reading"),
+            Self::Literal(literal) => {
+                let literal = literal.chars().collect::<Vec<_>>();
+                (start..line.len())
+                    .find(|&i| line[i..].starts_with(&literal))
+                    .map(|i| (i, i + literal.len()))
+            }
+            Self::Regex(regex) => {
+                let mut from = start;
+                loop {
+                    match regex.find_at(line, from)? {
+                        (start, end) if start == end => from = start + 1,
+                        found => return Some(found),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Streaming tokenizer that splits its input into lines, and lines on a [`Delimiter`].
+///
+/// Lines end at `\n`, so a `\r` before it is whitespace like any other.
+///
+/// Input is read through a fixed size buffer and only the token being built and the line it is
+/// on are kept in memory, so arbitrarily large inputs are tokenized in memory proportional to
+/// their longest token, or longest line with a delimiter, and tokens are produced as soon as they are terminated, even if the input never ends. With a
+/// delimiter other than whitespace the input is read a line at a time instead, and tokens are
+/// trimmed of surrounding spaces.
+///
+/// Tokens on the same line can be looked at ahead of time with [`Tokenizer::peek_on_line`], for
+/// numbers written across several tokens such as `12.50 CR`.
+pub struct Tokenizer<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    line: usize,
+    column: usize,
+    token: Vec<u8>,
+    /// The part of the current line consumed so far, unless it's longer than [`MAX_CONTEXT`].
+    current_line: Option<Vec<u8>>,
+    /// Number and contents of the last line ended, for tokens read ahead past its end.
+    previous_line: Option<(usize, Vec<u8>)>,
+    eof: bool,
+    delimiter: Delimiter,
+    /// Whitespace characters that are part of tokens instead of separating them.
+    word_chars: Vec<char>,
+    /// Tokens of the current line not returned yet, when splitting on a delimiter.
+    fields: VecDeque<Token>,
+    /// Tokens read ahead, with whether each is known to end its line.
+    peeked: VecDeque<(Token, bool)>,
+    /// Line of the last token returned, and whether it is known to end it.
+    last: (usize, bool),
+}
+
+impl<R: Read> Tokenizer<R> {
+    pub fn new(reader: R, delimiter: Delimiter) -> Self {
+        Self {
+            reader,
+            buf: vec![0; BUF_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            line: 1,
+            column: 1,
+            token: Vec::new(),
+            current_line: Some(Vec::new()),
+            previous_line: None,
+            eof: false,
+            delimiter,
+            word_chars: Vec::new(),
+            fields: VecDeque::new(),
+            peeked: VecDeque::new(),
+            last: (1, false),
+        }
+    }
+
+    /// Keeps the whitespace characters in `chars` within tokens, such as the no-break spaces
+    /// some locales group digits with.
+    pub fn with_word_chars(mut self, chars: &[char]) -> Self {
+        self.word_chars = chars.to_vec();
+        self
+    }
+
+    /// Whether the last token returned is known to be the last one on its line.
+    ///
+    /// Separators already buffered behind a token are consumed right away, so this is known
+    /// without waiting on more input whenever the line ending arrived together with the token.
+    pub fn at_line_end(&self) -> bool {
+        let (line, at_line_end) = self.last;
+        at_line_end
+            || self
+                .peeked
+                .front()
+                .is_some_and(|(next, _)| next.line > line)
+    }
+
+    /// The `n`th token after the last one returned, counting from 0, if it is on the same line.
+    ///
+    /// Only reads ahead as far as needed, and not at all once the line is known to have ended,
+    /// so peeking doesn't wait on input that isn't part of the line.
+    pub fn peek_on_line(&mut self, n: usize) -> io::Result<Option<&Token>> {
+        let line = self.last.0;
+        while self.peeked.len() <= n {
+            let line_ended = match self.peeked.back() {
+                Some((token, at_line_end)) => *at_line_end || token.line != line,
+                None => self.last.1,
+            };
+            if line_ended {
+                return Ok(None);
+            }
+
+            match self.read_token()? {
+                Some(token) => {
+                    let at_line_end = self.buffered_line_end();
+                    self.peeked.push_back((token, at_line_end));
+                }
+                None => return Ok(None),
+            }
+        }
+
+        Ok(self
+            .peeked
+            .get(n)
+            .map(|(token, _)| token)
+            .filter(|token| token.line == line))
+    }
+
+    /// The full line the last token returned is on, for pointing at it in error messages.
+    ///
+    /// Reads ahead to the end of the line if needed, keeping what was read for the following
+    /// tokens. Returns `None` if the line is too long to have been kept.
+    pub fn line_context(&mut self) -> io::Result<Option<String>> {
+        if self.last.0 != self.line {
+            let line = match &self.previous_line {
+                Some((number, line)) if *number == self.last.0 => line,
+                _ => return Ok(None),
+            };
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            return Ok(Some(String::from_utf8_lossy(line).into_owned()));
+        }
+
+        let Some(start_len) = self.current_line.as_ref().map(Vec::len) else {
+            return Ok(None);
+        };
+
+        let end = loop {
+            let rest = &self.buf[self.pos..self.len];
+            if start_len + rest.len() > MAX_CONTEXT {
+                return Ok(None);
+            }
+            match rest.iter().position(|&b| b == b'\n') {
+                Some(end) => break self.pos + end,
+                None if self.eof => break self.len,
+                None => self.read_more()?,
+            }
+        };
+
+        let start = self.current_line.as_deref().unwrap_or_default();
+        let line = [start, &self.buf[self.pos..end]].concat();
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        Ok(Some(String::from_utf8_lossy(line).into_owned()))
+    }
+
+    pub fn next_token(&mut self) -> io::Result<Option<Token>> {
+        let next = match self.peeked.pop_front() {
+            Some(next) => Some(next),
+            None => self.read_token()?.map(|token| {
+                let at_line_end = self.buffered_line_end();
+                (token, at_line_end)
+            }),
+        };
+
+        Ok(next.map(|(token, at_line_end)| {
+            self.last = (token.line, at_line_end);
+            token
+        }))
+    }
+
+    /// Whether a line ending follows the token just read, without reading more input.
+    fn buffered_line_end(&self) -> bool {
+        self.fields.is_empty() && self.buf[self.pos..self.len].first() == Some(&b'\n')
+    }
+
+    fn read_token(&mut self) -> io::Result<Option<Token>> {
+        if self.delimiter == Delimiter::Whitespace {
+            return self.next_word();
+        }
+
+        while self.fields.is_empty() {
+            if !self.read_fields()? {
+                return Ok(None);
+            }
+        }
+        Ok(self.fields.pop_front())
+    }
+
+    fn next_word(&mut self) -> io::Result<Option<Token>> {
+        self.token.clear();
+        let (mut line, mut column) = (self.line, self.column);
+
+        while self.fill_buf()? {
+            if self.token.is_empty() {
+                let chunk = &self.buf[self.pos..self.len];
+                let start = self.find_separator(chunk, false);
+                self.consume(start.unwrap_or(chunk.len()));
+                (line, column) = (self.line, self.column);
+                if start.is_none() {
+                    continue;
+                }
+            }
+
+            let chunk = &self.buf[self.pos..self.len];
+            match self.find_separator(chunk, true) {
+                Some(end) => {
+                    self.token.extend_from_slice(&chunk[..end]);
+                    self.consume(end);
+                    self.skip_buffered_separators();
+                    break;
+                }
+                None => {
+                    self.token.extend_from_slice(chunk);
+                    self.consume(chunk.len());
+                }
+            }
+        }
+
+        if self.token.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Token {
+            text: String::from_utf8_lossy(&self.token).into_owned(),
+            line,
+            column,
+        }))
+    }
+
+    /// Reads the next line and splits it on the delimiter, returning `false` at the end of the
+    /// input.
+    ///
+    /// The line ending is left buffered, so that line endings are detected the same as with
+    /// whitespace.
+    fn read_fields(&mut self) -> io::Result<bool> {
+        if self.fill_buf()? && self.buf[self.pos] == b'\n' {
+            self.consume(1);
+        }
+        if !self.fill_buf()? {
+            return Ok(false);
+        }
+
+        let line_number = self.line;
+        self.token.clear();
+        while self.fill_buf()? {
+            let chunk = &self.buf[self.pos..self.len];
+            let end = chunk.iter().position(|&b| b == b'\n');
+            self.token
+                .extend_from_slice(&chunk[..end.unwrap_or(chunk.len())]);
+            self.consume(end.unwrap_or(chunk.len()));
+            if end.is_some() {
+                break;
+            }
+        }
+
+        let line = String::from_utf8_lossy(&self.token)
+            .chars()
+            .collect::<Vec<_>>();
+        let mut start = 0;
+        while start <= line.len() {
+            let (end, next) = self
+                .delimiter
+                .find(&line, start)
+                .unwrap_or((line.len(), line.len() + 1));
+
+            let field = &line[start..end];
+            let leading = field.iter().take_while(|c| c.is_whitespace()).count();
+            let text = field[leading..].iter().collect::<String>();
+            let text = text.trim_end();
+            if !text.is_empty() {
+                self.fields.push_back(Token {
+                    text: text.to_owned(),
+                    line: line_number,
+                    column: start + leading + 1,
+                });
+            }
+
+            start = next;
+        }
+
+        Ok(true)
+    }
+
+    /// Consumes the separators on the same line following a token without reading more input,
+    /// stopping at a line ending so [`Tokenizer::at_line_end`] can report it.
+    fn skip_buffered_separators(&mut self) {
+        let chunk = &self.buf[self.pos..self.len];
+        let line_end = chunk.iter().position(|&b| b == b'\n');
+        let chunk = &chunk[..line_end.unwrap_or(chunk.len())];
+        let skipped = self.find_separator(chunk, false).unwrap_or(chunk.len());
+        self.consume(skipped);
+    }
+
+    /// Position of the first separator in `bytes` if `separator` is `true`, or of the first
+    /// byte after the separators at its start if it is `false`.
+    fn find_separator(&self, bytes: &[u8], separator: bool) -> Option<usize> {
+        let mut i = 0;
+        while i < bytes.len() {
+            match self.separator_len(&bytes[i..]) {
+                Some(_) if separator => return Some(i),
+                Some(len) => i += len,
+                None if separator => i += 1,
+                None => return Some(i),
+            }
+        }
+        None
+    }
+
+    /// Length in bytes of the whitespace character at the start of `bytes`, if it is one that
+    /// separates tokens.
+    fn separator_len(&self, bytes: &[u8]) -> Option<usize> {
+        let (c, len) = match bytes[0] {
+            b if b.is_ascii() => (char::from(b), 1),
+            b => {
+                let len = if b >= 0xF0 {
+                    4
+                } else if b >= 0xE0 {
+                    3
+                } else {
+                    2
+                };
+                let c = str::from_utf8(bytes.get(..len)?).ok()?.chars().next()?;
+                (c, len)
+            }
+        };
+
+        (c.is_whitespace() && !self.word_chars.contains(&c)).then_some(len)
+    }
+
+    /// Advances past the next `n` buffered bytes, keeping track of the line and column.
+    fn consume(&mut self, n: usize) {
+        let bytes = &self.buf[self.pos..self.pos + n];
+        self.pos += n;
+
+        let line_start = match bytes.iter().rposition(|&b| b == b'\n') {
+            Some(i) => {
+                let first = bytes.iter().position(|&b| b == b'\n').unwrap_or(i);
+                let mut ended = self.current_line.take();
+                extend_context(&mut ended, &bytes[..first]);
+                self.previous_line = ended.map(|ended| (self.line, ended));
+
+                self.line += count_newlines(&bytes[..=i]);
+                self.column = 1;
+                self.current_line = Some(Vec::new());
+                i + 1
+            }
+            None => 0,
+        };
+
+        let bytes = &bytes[line_start..];
+        self.column += count_chars(bytes);
+        extend_context(&mut self.current_line, bytes);
+    }
+
+    /// Refills the buffer once it is used up, returning `false` at the end of the input.
+    ///
+    /// A character split across reads is completed first, so that a multi-byte whitespace
+    /// character is never seen in halves.
+    fn fill_buf(&mut self) -> io::Result<bool> {
+        while self.pos == self.len && !self.eof {
+            (self.pos, self.len) = (0, 0);
+            self.read_more()?;
+        }
+        while !self.eof && ends_mid_char(&self.buf[self.pos..self.len]) {
+            self.read_more()?;
+        }
+
+        Ok(self.pos < self.len)
+    }
+
+    /// Reads more input behind the unconsumed bytes, moving them to the front of the buffer if
+    /// there's no room left after them.
+    fn read_more(&mut self) -> io::Result<()> {
+        if self.len == self.buf.len() {
+            self.buf.copy_within(self.pos..self.len, 0);
+            self.len -= self.pos;
+            self.pos = 0;
+        }
+
+        loop {
+            match self.reader.read(&mut self.buf[self.len..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    return Ok(());
+                }
+                Ok(n) => {
+                    self.len += n;
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for Tokenizer<R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
+/// Appends `bytes` to a line kept for context, dropping it once it gets longer than
+/// [`MAX_CONTEXT`].
+fn extend_context(line: &mut Option<Vec<u8>>, bytes: &[u8]) {
+    if let Some(kept) = line {
+        if kept.len() + bytes.len() > MAX_CONTEXT {
+            *line = None;
+        } else {
+            kept.extend_from_slice(bytes);
+        }
+    }
+}
+
+/// Whether `bytes` ends with the start of a UTF-8 character whose other bytes are missing.
+fn ends_mid_char(bytes: &[u8]) -> bool {
+    let continuation = bytes
+        .iter()
+        .rev()
+        .take_while(|&&b| b & 0xC0 == 0x80)
+        .count();
+    match bytes.len().checked_sub(continuation + 1).map(|i| bytes[i]) {
+        Some(b) if b >= 0xF0 => continuation < 3,
+        Some(b) if b >= 0xE0 => continuation < 2,
+        Some(b) if b >= 0xC0 => continuation < 1,
+        _ => false,
+    }
+}
+
+fn count_newlines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| b == b'\n').count()
+}
+
+/// Counts UTF-8 characters by skipping continuation bytes.
+fn count_chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| b & 0xC0 != 0x80).count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reader handing out one byte per read, to exercise tokens split across buffer refills.
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    fn tokens(reader: impl Read) -> Vec<(String, usize, usize)> {
+        tokens_delimited_by(reader, Delimiter::Whitespace)
+    }
+
+    fn tokens_delimited_by(reader: impl Read, delimiter: Delimiter) -> Vec<(String, usize, usize)> {
+        Tokenizer::new(reader, delimiter)
+            .map(|token| {
+                let token = token.unwrap();
+                (token.text, token.line, token.column)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn can_tokenize_across_buffer_refills() {
+        let input = "  12 3,4\n\n é5.6  \n7".as_bytes();
+        let expected = vec![
+            ("12".to_owned(), 1, 3),
+            ("3,4".to_owned(), 1, 6),
+            ("é5.6".to_owned(), 3, 2),
+            ("7".to_owned(), 4, 1),
+        ];
+
+        assert_eq!(tokens(input), expected);
+        assert_eq!(tokens(ByteReader(input)), expected);
+    }
+
+    #[test]
+    fn stops_at_buffered_line_endings_after_a_token() {
+        let mut tokenizer = Tokenizer::new(&b"1 2  \n3"[..], Delimiter::Whitespace);
+
+        tokenizer.next_token().unwrap();
+        assert!(!tokenizer.at_line_end());
+        tokenizer.next_token().unwrap();
+        assert!(tokenizer.at_line_end());
+    }
+
+    #[test]
+    fn can_provide_the_line_of_the_last_token() {
+        let mut tokenizer = Tokenizer::new(&b"1 2\n3 x 4\r\n5"[..], Delimiter::Whitespace);
+
+        for _ in 0..4 {
+            tokenizer.next_token().unwrap();
+        }
+        assert_eq!(tokenizer.line_context().unwrap(), Some("3 x 4".to_owned()));
+
+        for _ in 0..2 {
+            tokenizer.next_token().unwrap();
+        }
+        assert_eq!(tokenizer.line_context().unwrap(), Some("5".to_owned()));
+    }
+
+    #[test]
+    fn reads_ahead_to_the_end_of_the_line_for_context() {
+        let mut tokenizer = Tokenizer::new(ByteReader(b"1 x 2 3\n4"), Delimiter::Whitespace);
+
+        tokenizer.next_token().unwrap();
+        let token = tokenizer.next_token().unwrap().unwrap();
+        assert_eq!(token.text, "x");
+        assert_eq!(
+            tokenizer.line_context().unwrap(),
+            Some("1 x 2 3".to_owned())
+        );
+
+        let rest = tokens_from(&mut tokenizer);
+        assert_eq!(rest, vec!["2", "3", "4"]);
+    }
+
+    fn tokens_from<R: Read>(tokenizer: &mut Tokenizer<R>) -> Vec<String> {
+        tokenizer.map(|token| token.unwrap().text).collect()
+    }
+
+    #[test]
+    fn can_split_lines_on_a_delimiter() {
+        let input = "3,4, 5\n\n,6 ,\r\n7".as_bytes();
+        let expected = vec![
+            ("3".to_owned(), 1, 1),
+            ("4".to_owned(), 1, 3),
+            ("5".to_owned(), 1, 6),
+            ("6".to_owned(), 3, 2),
+            ("7".to_owned(), 4, 1),
+        ];
+
+        let comma = Delimiter::Literal(",".to_owned());
+        assert_eq!(tokens_delimited_by(input, comma.clone()), expected);
+        assert_eq!(tokens_delimited_by(ByteReader(input), comma), expected);
+
+        let regex = Delimiter::Regex(r"\s*[;|]\s*".parse().unwrap());
+        assert_eq!(
+            tokens_delimited_by(&b"1 ; 2|3 4"[..], regex),
+            vec![
+                ("1".to_owned(), 1, 1),
+                ("2".to_owned(), 1, 5),
+                ("3 4".to_owned(), 1, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn knows_line_ends_and_context_when_delimited() {
+        let mut tokenizer = Tokenizer::new(&b"1\t2\n3"[..], Delimiter::Literal("\t".to_owned()));
+
+        tokenizer.next_token().unwrap();
+        assert!(!tokenizer.at_line_end());
+        tokenizer.next_token().unwrap();
+        assert!(tokenizer.at_line_end());
+        assert_eq!(tokenizer.line_context().unwrap(), Some("1\t2".to_owned()));
+        assert_eq!(tokenizer.next_token().unwrap().unwrap().line, 2);
+    }
+
+    #[test]
+    fn splits_on_any_unicode_whitespace() {
+        let input = "1\t2\r\n3\u{3000}4\u{A0}5\u{2003}\u{2003}6\r\n".as_bytes();
+        let expected = vec![
+            ("1".to_owned(), 1, 1),
+            ("2".to_owned(), 1, 3),
+            ("3".to_owned(), 2, 1),
+            ("4".to_owned(), 2, 3),
+            ("5".to_owned(), 2, 5),
+            ("6".to_owned(), 2, 8),
+        ];
+
+        assert_eq!(tokens(input), expected);
+        assert_eq!(tokens(ByteReader(input)), expected);
+    }
+
+    #[test]
+    fn keeps_word_chars_within_tokens() {
+        let tokenizer =
+            Tokenizer::new(ByteReader("1\u{A0}000 2".as_bytes()), Delimiter::Whitespace)
+                .with_word_chars(&['\u{A0}']);
+        let tokens = tokenizer
+            .map(|token| token.unwrap().text)
+            .collect::<Vec<_>>();
+
+        assert_eq!(tokens, vec!["1\u{A0}000", "2"]);
+    }
+
+    #[test]
+    fn stops_at_crlf_line_endings_after_a_token() {
+        let mut tokenizer = Tokenizer::new(&b"1\t\r\n2"[..], Delimiter::Whitespace);
+
+        tokenizer.next_token().unwrap();
+        assert!(tokenizer.at_line_end());
+    }
+
+    #[test]
+    fn can_peek_at_tokens_on_the_same_line() {
+        let mut tokenizer = Tokenizer::new(ByteReader(b"1 CR x\n2 y\n"), Delimiter::Whitespace);
+
+        tokenizer.next_token().unwrap();
+        assert_eq!(tokenizer.peek_on_line(1).unwrap().unwrap().text, "x");
+        assert_eq!(tokenizer.peek_on_line(2).unwrap(), None);
+        assert_eq!(tokenizer.next_token().unwrap().unwrap().text, "CR");
+        tokenizer.next_token().unwrap();
+        assert!(tokenizer.at_line_end());
+        assert_eq!(tokenizer.line_context().unwrap(), Some("1 CR x".to_owned()));
+
+        assert_eq!(tokenizer.next_token().unwrap().unwrap().line, 2);
+        assert!(!tokenizer.at_line_end());
+        assert_eq!(tokenizer.peek_on_line(0).unwrap().unwrap().text, "y");
+    }
+
+    #[test]
+    fn keeps_the_context_of_a_line_peeked_past() {
+        let mut tokenizer = Tokenizer::new(ByteReader(b"1 x \n2"), Delimiter::Whitespace);
+
+        tokenizer.next_token().unwrap();
+        tokenizer.next_token().unwrap();
+        // One byte at a time, the line ending isn't buffered yet after the space ending `x`
+        assert!(!tokenizer.at_line_end());
+        assert_eq!(tokenizer.peek_on_line(0).unwrap(), None);
+        assert!(tokenizer.at_line_end());
+        assert_eq!(tokenizer.line_context().unwrap(), Some("1 x ".to_owned()));
+    }
+
+    #[test]
+    fn yields_nothing_for_blank_input() {
+        assert!(tokens(&b" \n \n"[..]).is_empty());
+        assert!(tokens("\t\r\n\u{2028}".as_bytes()).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use std::{fmt, ops::AddAssign, str::FromStr};
+
+use crate::{decimal::Decimal, number::Number, precision::Rounding, rational::Rational};
+
+/// Ways an uncertainty is written after its value, as in `1.5±0.1` or `1.5+/-0.1`.
+pub const SEPARATORS: &[&str] = &["±", "+/-"];
+
+/// Measured value with an uncertainty, summed by the uncertainty mode.
+///
+/// Values are summed exactly as [`Decimal`]s, and so are the uncertainties and their squares,
+/// so the uncertainty of the total can be given for independent errors (the root of the sum of
+/// squares) or for correlated ones (the sum) once everything is added up.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Uncertain {
+    value: Decimal,
+    /// Sum of the uncertainties.
+    linear: Decimal,
+    /// Sum of the squares of the uncertainties.
+    squares: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseUncertainError;
+
+impl Uncertain {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// `value` with an uncertainty of `uncertainty`, which is at least zero.
+    pub fn new(value: Decimal, uncertainty: Decimal) -> Self {
+        let squares = uncertainty.scaled_by(
+            &Rational::from(uncertainty.clone()),
+            2 * uncertainty.scale(),
+        );
+        Self {
+            value,
+            linear: uncertainty,
+            squares,
+        }
+    }
+
+    pub fn value(&self) -> &Decimal {
+        &self.value
+    }
+
+    /// Product with `factor`, such as the size of a unit, rounded to `scale` decimal places.
+    pub fn scaled_by(&self, factor: &Rational, scale: u32) -> Self {
+        let (magnitude, square) = magnitude_and_square(factor);
+        Self {
+            value: self.value.scaled_by(factor, scale),
+            linear: self.linear.scaled_by(&magnitude, scale),
+            squares: self.squares.scaled_by(&square, 2 * scale),
+        }
+    }
+
+    /// Product with `factor`, or `None` if it has infinitely many decimal places.
+    pub fn scaled_exactly(&self, factor: &Rational) -> Option<Self> {
+        let (magnitude, square) = magnitude_and_square(factor);
+        Some(Self {
+            value: Number::scaled_exactly(&self.value, factor)?,
+            linear: Number::scaled_exactly(&self.linear, &magnitude)?,
+            squares: Number::scaled_exactly(&self.squares, &square)?,
+        })
+    }
+
+    /// Formats the value and its uncertainty as `3.8 ± 0.2`. The uncertainty is the root sum of
+    /// squares of those summed, or their sum if they are `correlated`.
+    ///
+    /// Both are rounded as `rounded` says if set. Otherwise the value is printed as is, and the
+    /// uncertainty with as many decimal places as the most precise value or uncertainty.
+    pub fn format(&self, correlated: bool, rounded: Option<(u32, Rounding)>) -> String {
+        let value = match rounded {
+            Some((places, rounding)) => {
+                Decimal::round(&Rational::from(self.value.clone()), places, rounding)
+            }
+            None => self.value.clone(),
+        };
+
+        let (places, rounding) = rounded.unwrap_or((
+            self.value.scale().max(self.linear.scale()),
+            Rounding::HalfEven,
+        ));
+        let uncertainty = if correlated {
+            Decimal::round(&Rational::from(self.linear.clone()), places, rounding)
+        } else {
+            self.squares.sqrt(places, rounding)
+        };
+        format!("{} ± {}", value, uncertainty)
+    }
+}
+
+impl FromStr for Uncertain {
+    type Err = ParseUncertainError;
+
+    /// Parses a decimal followed by an uncertainty, as `1.5±0.1`, `1.5 ± 0.1` or `1.5+/-0.1`, or
+    /// a decimal alone, whose uncertainty is zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, uncertainty) = match split_uncertainty(s) {
+            Some((value, uncertainty)) => (value, Some(uncertainty)),
+            None => (s, None),
+        };
+
+        let value = value.trim_end().parse().map_err(|_| ParseUncertainError)?;
+        let uncertainty = match uncertainty {
+            // A sign would be ambiguous, as the uncertainty goes both ways
+            Some(uncertainty) if uncertainty.trim_start().starts_with(['-', '+']) => {
+                return Err(ParseUncertainError)
+            }
+            Some(uncertainty) => uncertainty
+                .trim_start()
+                .parse()
+                .map_err(|_| ParseUncertainError)?,
+            None => Decimal::zero(),
+        };
+        Ok(Self::new(value, uncertainty))
+    }
+}
+
+/// Absolute value and square of `factor`, which uncertainties and their squares are scaled by.
+fn magnitude_and_square(factor: &Rational) -> (Rational, Rational) {
+    let magnitude = Rational::new(factor.numer().abs(), factor.denom().clone())
+        .expect("the denominator isn't zero");
+    (magnitude, factor * factor)
+}
+
+/// Splits `s` into the value and uncertainty on either side of a separator, as `1.5` and `0.1`
+/// for `1.5±0.1`.
+pub fn split_uncertainty(s: &str) -> Option<(&str, &str)> {
+    SEPARATORS
+        .iter()
+        .find_map(|separator| s.split_once(separator))
+}
+
+impl AddAssign<&Uncertain> for Uncertain {
+    fn add_assign(&mut self, rhs: &Uncertain) {
+        self.value += &rhs.value;
+        self.linear += &rhs.linear;
+        self.squares += &rhs.squares;
+    }
+}
+
+impl fmt::Display for Uncertain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format(false, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_values_with_uncertainties() {
+        for (s, expected) in [
+            ("1.5±0.1", Some("1.5 ± 0.1")),
+            ("1.5 ± 0.1", Some("1.5 ± 0.1")),
+            ("-2+/-0.25", Some("-2 ± 0.25")),
+            ("12", Some("12 ± 0")),
+            ("1.5±-0.1", None),
+            ("1.5±", None),
+            ("±0.1", None),
+            ("1.5±0.1±0.2", None),
+        ] {
+            let parsed = s.parse::<Uncertain>().ok().map(|n| n.format(true, None));
+            assert_eq!(parsed.as_deref(), expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn propagates_uncertainties() {
+        let mut total = Uncertain::zero();
+        for s in ["1.5±0.3", "2.25±0.4", "0.75"] {
+            total += &s.parse().unwrap();
+        }
+
+        assert_eq!(total.format(false, None), "4.50 ± 0.50");
+        assert_eq!(total.format(true, None), "4.50 ± 0.70");
+        assert_eq!(
+            total.format(false, Some((1, Rounding::HalfEven))),
+            "4.5 ± 0.5"
+        );
+        assert_eq!(total.format(true, Some((0, Rounding::Ceil))), "5 ± 1");
+
+        // Far past what a float can square
+        let mut total = "1e200±1e200".parse::<Uncertain>().unwrap();
+        total += &"1".parse().unwrap();
+        let expected = format!("1{}1", "0".repeat(199));
+        assert_eq!(
+            total.format(false, Some((2, Rounding::HalfEven))),
+            format!("{}.00 ± 1{}.00", expected, "0".repeat(200))
+        );
+        assert_eq!(
+            total.format(false, None),
+            format!("{} ± 1{}", expected, "0".repeat(200))
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+
+use crate::{number::Number, precision::scaled_places, rational::Rational};
+
+/// Decimal places totals are rounded to when converted to a unit they can't be held in exactly,
+/// as metres can't be in feet.
+const UNIT_SCALE: u32 = 6;
+
+/// Physical quantity that amounts with a unit are a measure of. Only amounts of the same
+/// dimension are added up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Mass,
+    Length,
+    Time,
+    Volume,
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Mass => "mass",
+            Self::Length => "length",
+            Self::Time => "time",
+            Self::Volume => "volume",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Unit of measure that amounts such as `3.2kg` or `12 m` are given in.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    pub dimension: Dimension,
+    /// Number of base units in one of this unit, as a decimal so every unit converts to the base
+    /// unit exactly.
+    base_units: &'static str,
+    /// Whether totals are printed in it when no unit is chosen.
+    shown: bool,
+    /// Whether it is also an English word, as `in` is, so that it is only read as the unit of
+    /// the number before it when written apart if no word follows, unlike in `3 in stock`.
+    word: bool,
+}
+
+const fn unit(
+    symbol: &'static str,
+    dimension: Dimension,
+    base_units: &'static str,
+    shown: bool,
+) -> Unit {
+    let word = matches!(symbol.as_bytes(), b"in" | b"min");
+    Unit {
+        symbol,
+        dimension,
+        base_units,
+        shown,
+        word,
+    }
+}
+
+/// Units that are recognized, by dimension and from small to large. The first of each dimension
+/// with one base unit in it is the base unit amounts are summed in.
+const UNITS: &[Unit] = &[
+    unit("µg", Dimension::Mass, "0.000001", true),
+    unit("μg", Dimension::Mass, "0.000001", false),
+    unit("mg", Dimension::Mass, "0.001", true),
+    unit("g", Dimension::Mass, "1", true),
+    unit("oz", Dimension::Mass, "28.349523125", false),
+    unit("lb", Dimension::Mass, "453.59237", false),
+    unit("kg", Dimension::Mass, "1000", true),
+    unit("t", Dimension::Mass, "1000000", true),
+    unit("µm", Dimension::Length, "0.000001", true),
+    unit("μm", Dimension::Length, "0.000001", false),
+    unit("mm", Dimension::Length, "0.001", true),
+    unit("cm", Dimension::Length, "0.01", true),
+    unit("in", Dimension::Length, "0.0254", false),
+    unit("ft", Dimension::Length, "0.3048", false),
+    unit("yd", Dimension::Length, "0.9144", false),
+    unit("m", Dimension::Length, "1", true),
+    unit("km", Dimension::Length, "1000", true),
+    unit("mi", Dimension::Length, "1609.344", false),
+    unit("µs", Dimension::Time, "0.000001", true),
+    unit("μs", Dimension::Time, "0.000001", false),
+    unit("ms", Dimension::Time, "0.001", true),
+    unit("s", Dimension::Time, "1", true),
+    unit("min", Dimension::Time, "60", true),
+    unit("h", Dimension::Time, "3600", true),
+    unit("d", Dimension::Time, "86400", true),
+    unit("mL", Dimension::Volume, "0.001", true),
+    unit("ml", Dimension::Volume, "0.001", false),
+    unit("cL", Dimension::Volume, "0.01", true),
+    unit("cl", Dimension::Volume, "0.01", false),
+    unit("L", Dimension::Volume, "1", true),
+    unit("l", Dimension::Volume, "1", false),
+    unit("gal", Dimension::Volume, "3.785411784", false),
+];
+
+impl Unit {
+    /// Number of base units in one of this unit.
+    pub fn base_units(&self) -> Rational {
+        self.base_units.parse().expect("unit sizes are decimals")
+    }
+
+    /// Whether it is also an English word, as `in` is.
+    pub fn is_word(&self) -> bool {
+        self.word
+    }
+
+    /// Unit that amounts of this one's dimension are summed in.
+    pub fn base(&self) -> &'static Unit {
+        UNITS
+            .iter()
+            .find(|unit| unit.dimension == self.dimension && unit.base_units == "1")
+            .expect("every dimension has a base unit")
+    }
+
+    /// Number of this unit that `n` base units make, exactly if the type can hold it and
+    /// otherwise rounded.
+    pub fn amount_of<N: Number>(&self, n: &N) -> N {
+        let factor = self.per_base_unit();
+        n.scaled_exactly(&factor)
+            .unwrap_or_else(|| n.scaled_by(&factor, UNIT_SCALE))
+    }
+
+    /// Unit a total of `n` of this base unit is printed in when none is chosen: the largest
+    /// metric unit, or minute, hour or day, that it is at least one of and converts to exactly.
+    pub fn pick_for<N: Number>(&'static self, n: &N) -> &'static Unit {
+        let magnitude = n.to_f64().abs();
+        let fits = |unit: &&Unit| {
+            magnitude >= unit.base_units().to_f64()
+                && n.scaled_exactly(&unit.per_base_unit()).is_some()
+        };
+
+        let units = UNITS.iter().filter(|unit| unit.dimension == self.dimension);
+        units
+            .filter(|unit| unit.shown)
+            .rev()
+            .find(fits)
+            .unwrap_or(self)
+    }
+
+    /// Number of decimal places an amount written with `places` of them in the base unit has in
+    /// this unit.
+    pub fn places_of(&self, places: i64) -> i64 {
+        scaled_places(places, &self.per_base_unit())
+    }
+
+    fn per_base_unit(&self) -> Rational {
+        let base_units = self.base_units();
+        Rational::new(base_units.denom().clone(), base_units.numer().clone())
+            .expect("units aren't zero")
+    }
+}
+
+/// Unit with the symbol `symbol`, such as `kg` or `ft`.
+pub fn unit_of(symbol: &str) -> Option<&'static Unit> {
+    UNITS.iter().find(|unit| unit.symbol == symbol)
+}
+
+/// Splits the unit off an amount such as `3.2kg`, `1e3g` or `450 kg`, returning the amount
+/// without it.
+pub fn split_unit(num_str: &str) -> Option<(&str, &'static Unit)> {
+    let number = num_str.trim_end_matches(|c: char| c.is_alphabetic());
+    let unit = unit_of(&num_str[number.len()..])?;
+
+    let number = number.strip_suffix(' ').unwrap_or(number);
+    // Letters other than the exponent of `1e3` mean the unit is part of something else, as the
+    // `d` of `0x1d` is
+    let (mantissa, exponent) = number.split_once(['e', 'E']).unwrap_or((number, ""));
+    if !number.ends_with(|c: char| c.is_ascii_digit() || c == '.')
+        || mantissa.is_empty()
+        || format!("{}{}", mantissa, exponent).contains(|c: char| c.is_alphabetic())
+    {
+        return None;
+    }
+    Some((number, unit))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decimal::Decimal;
+
+    #[test]
+    fn splits_units_off_amounts() {
+        for (num_str, expected) in [
+            ("3.2kg", Some(("3.2", "kg"))),
+            ("450g", Some(("450", "g"))),
+            ("450 kg", Some(("450", "kg"))),
+            ("-12m", Some(("-12", "m"))),
+            ("-12 m", Some(("-12", "m"))),
+            ("3 in", Some(("3", "in"))),
+            ("1e3g", Some(("1e3", "g"))),
+            ("1µm", Some(("1", "µm"))),
+            ("1e3e3g", None),
+            ("e3g", None),
+            ("5km", Some(("5", "km"))),
+            ("1/2 gal", Some(("1/2", "gal"))),
+            ("12 kgs", None),
+            ("0x1d", None),
+            ("kg", None),
+        ] {
+            let split = split_unit(num_str).map(|(n, unit)| (n, unit.symbol));
+            assert_eq!(split, expected, "{}", num_str);
+        }
+    }
+
+    #[test]
+    fn converts_from_base_units_and_picks_a_unit_to_print() {
+        let dec = |s: &str| s.parse::<Decimal>().unwrap();
+        let unit = |symbol| unit_of(symbol).unwrap();
+
+        assert_eq!(unit("kg").base().symbol, "g");
+        assert_eq!(unit("ft").amount_of(&dec("1.524")).to_string(), "5");
+        assert_eq!(unit("ft").amount_of(&dec("1")).to_string(), "3.280840");
+        assert_eq!(unit("kg").places_of(-2), 1);
+        assert_eq!(unit("mg").places_of(1), -2);
+
+        for (base, total, expected) in [
+            ("g", "3650", "kg"),
+            ("g", "0.45", "mg"),
+            ("m", "5012", "km"),
+            ("m", "0.3048", "cm"),
+            ("s", "5400", "h"),
+            ("s", "100", "s"),
+            ("L", "0", "L"),
+        ] {
+            assert_eq!(
+                unit(base).pick_for(&dec(total)).symbol,
+                expected,
+                "{}",
+                total
+            );
+        }
+    }
+}
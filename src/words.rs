@@ -0,0 +1,245 @@
+use crate::{integer::Integer, rational::Rational};
+
+/// Numbers below twenty, by value.
+const SMALL: &[&str] = &[
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+/// Multiples of ten from twenty, by value over ten.
+const TENS: &[&str] = &[
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Scale words and suffixes such as the `k` of `2.5k`, with the power of ten they stand for. A
+/// `K` isn't one, as it is a power of 1024 in sizes.
+const SCALES: &[(&str, u32)] = &[
+    ("thousand", 3),
+    ("million", 6),
+    ("billion", 9),
+    ("trillion", 12),
+];
+const SUFFIXES: &[(&str, u32)] = &[
+    ("k", 3),
+    ("M", 6),
+    ("mn", 6),
+    ("B", 9),
+    ("bn", 9),
+    ("T", 12),
+    ("tn", 12),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Word {
+    Small(u32),
+    Tens(u32),
+    Hundred,
+    Scale(u32),
+    And,
+    /// The `a` of `a hundred`.
+    A,
+    Numeral(Rational),
+}
+
+/// Value of a number written in English words, such as `twelve`, `one hundred and five`,
+/// `twenty-one`, `3 million` or `2.5k`, or `None` if `s` isn't one. Numerals such as the `3` of
+/// `3 million` are read with `numeral`.
+pub fn parse_words(s: &str, numeral: impl Fn(&str) -> Option<Rational>) -> Option<Rational> {
+    let (negative, s) = match s.split_once(' ') {
+        Some(("minus" | "negative", rest)) => (true, rest),
+        _ => match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        },
+    };
+
+    let value = match suffixed(s, &numeral) {
+        Some(value) => value,
+        None => {
+            let words = s
+                .split([' ', '-'])
+                .filter(|word| !word.is_empty())
+                .map(|word| parse_word(word, &numeral))
+                .collect::<Option<Vec<_>>>()?;
+            value_of(&words)?
+        }
+    };
+
+    if negative {
+        Some(&value * &Rational::from(Integer::Small(-1)))
+    } else {
+        Some(value)
+    }
+}
+
+/// Value of a numeral with a scale suffix, such as `2.5k` or `3bn`.
+fn suffixed(s: &str, numeral: impl Fn(&str) -> Option<Rational>) -> Option<Rational> {
+    let number = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let (_, exp) = SUFFIXES
+        .iter()
+        .find(|&&(suffix, _)| suffix == &s[number.len()..])?;
+    if !number.ends_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(&numeral(number)? * &power_of_ten(*exp))
+}
+
+fn parse_word(word: &str, numeral: impl Fn(&str) -> Option<Rational>) -> Option<Word> {
+    if word.starts_with(|c: char| c.is_ascii_digit()) {
+        return numeral(word).map(Word::Numeral);
+    }
+
+    let word = word.to_ascii_lowercase();
+    let position = |words: &[&str]| words.iter().position(|&w| w == word);
+    if let Some(n) = position(SMALL) {
+        return Some(Word::Small(n as u32));
+    }
+    if let Some(n) = position(TENS) {
+        return Some(Word::Tens(20 + 10 * n as u32));
+    }
+    if let Some(&(_, exp)) = SCALES.iter().find(|&&(scale, _)| scale == word) {
+        return Some(Word::Scale(exp));
+    }
+    match word.as_str() {
+        "hundred" => Some(Word::Hundred),
+        "and" => Some(Word::And),
+        "a" => Some(Word::A),
+        _ => None,
+    }
+}
+
+/// Value of a sequence of number words, or `None` if they don't make up a single number, as
+/// `one two` or `thousand million` don't.
+fn value_of(words: &[Word]) -> Option<Rational> {
+    // Numerals alone are left to be read as numbers
+    if words.iter().all(|word| matches!(word, Word::Numeral(_))) {
+        return None;
+    }
+
+    let mut total = Rational::zero();
+    // Part below the last scale word, such as the `two hundred` of `one thousand two hundred`
+    let mut group = Rational::zero();
+    let mut has_hundred = false;
+    let mut last_scale = None;
+    let mut prev: Option<&Word> = None;
+    for word in words {
+        // Whether `prev` is a number that a hundred or scale word can multiply
+        let multipliable = match prev {
+            Some(Word::Small(n)) => *n > 0,
+            Some(Word::Tens(_) | Word::Numeral(_) | Word::A) => true,
+            Some(Word::Hundred) => matches!(word, Word::Scale(_)),
+            _ => false,
+        };
+        let starts_part = matches!(
+            prev,
+            None | Some(Word::Hundred | Word::Scale(_) | Word::And)
+        );
+
+        match word {
+            Word::Small(0) if prev.is_none() && words.len() == 1 => {}
+            Word::Small(0) => return None,
+            Word::Small(n) => {
+                let after_tens = matches!(prev, Some(Word::Tens(_))) && *n < 10;
+                if !starts_part && !after_tens {
+                    return None;
+                }
+                group += &Rational::from(Integer::Small((*n).into()));
+            }
+            Word::Tens(n) if starts_part => group += &Rational::from(Integer::Small((*n).into())),
+            Word::Hundred if multipliable && !has_hundred => {
+                if prev == Some(&Word::A) {
+                    group = Rational::from(Integer::Small(1));
+                }
+                group = &group * &power_of_ten(2);
+                has_hundred = true;
+            }
+            Word::Scale(exp) if multipliable && last_scale.is_none_or(|last| last > *exp) => {
+                if prev == Some(&Word::A) {
+                    group = Rational::from(Integer::Small(1));
+                }
+                total += &(&group * &power_of_ten(*exp));
+                group = Rational::zero();
+                has_hundred = false;
+                last_scale = Some(*exp);
+            }
+            Word::And if matches!(prev, Some(Word::Hundred | Word::Scale(_))) => {}
+            Word::A if prev.is_none() => {}
+            Word::Numeral(n) if matches!(prev, None | Some(Word::Scale(_))) => group = n.clone(),
+            _ => return None,
+        }
+        prev = Some(word);
+    }
+
+    // A number can't end waiting for more, as `one hundred and`, `a` or the `3` of `3 million`
+    // would
+    if matches!(prev, Some(Word::And | Word::A | Word::Numeral(_))) {
+        return None;
+    }
+    total += &group;
+    Some(total)
+}
+
+fn power_of_ten(exp: u32) -> Rational {
+    Rational::from(Integer::Small(1).mul_pow10(exp))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn words(s: &str) -> Option<String> {
+        parse_words(s, |numeral| numeral.parse().ok()).map(|n| n.to_string())
+    }
+
+    #[test]
+    fn reads_numbers_in_words() {
+        for (s, expected) in [
+            ("twelve", Some("12")),
+            ("Zero", Some("0")),
+            ("one hundred and five", Some("105")),
+            ("twenty-one", Some("21")),
+            ("a thousand", Some("1000")),
+            ("one thousand two hundred thirty-four", Some("1234")),
+            ("two million three hundred thousand", Some("2300000")),
+            ("one hundred thousand", Some("100000")),
+            ("twelve hundred", Some("1200")),
+            ("one thousand and one", Some("1001")),
+            ("minus seven", Some("-7")),
+            ("3 million", Some("3000000")),
+            ("2.5 billion", Some("2500000000")),
+            ("2.5k", Some("2500")),
+            ("-1.5M", Some("-1500000")),
+            ("3", None),
+            ("one two", None),
+            ("five and six", None),
+            ("hundred", None),
+            ("thousand million", None),
+            ("one thousand million", None),
+            ("twenty zero", None),
+            ("one hundred and", None),
+            ("3 5", None),
+            ("2.5kg", None),
+            ("2K", None),
+        ] {
+            assert_eq!(words(s).as_deref(), expected, "{}", s);
+        }
+    }
+}